@@ -28,9 +28,13 @@ fn create_runtime() -> JsRuntime {
     let extensions = vec![
         ext_webidl::init(),
         ext_web::init(),
+        ext_event::init(),
         ext_timers::init(),
         ext_resources::init(),
-        ext_console::init()
+        ext_console::init(),
+        ext_url::init(),
+        ext_fetch::init(),
+        ext_crypto::init(),
     ];
 
     let mut runtime = JsRuntime::new(RuntimeOptions {
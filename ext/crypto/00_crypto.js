@@ -0,0 +1,255 @@
+"use strict";
+
+((window) => {
+    const bootstrap = window.__bootstrap;
+    const core = window.Deno.core;
+    const webidl = bootstrap.webidl;
+    const {DOMException} = bootstrap.domException;
+    const {
+        ArrayBufferIsView,
+        ArrayBufferPrototypeSlice,
+        ArrayPrototypeEvery,
+        ArrayPrototypeFind,
+        ArrayPrototypeIncludes,
+        BigInt64Array,
+        BigUint64Array,
+        Int16Array,
+        Int32Array,
+        Int8Array,
+        ObjectAssign,
+        ObjectCreate,
+        ObjectFreeze,
+        String,
+        StringPrototypeToUpperCase,
+        Symbol,
+        SymbolToStringTag,
+        TypeError,
+        Uint16Array,
+        Uint32Array,
+        Uint8Array,
+        Uint8ClampedArray,
+    } = bootstrap.primordials;
+
+    const DIGEST_ALGORITHMS = ["SHA-1", "SHA-256", "SHA-384", "SHA-512"];
+    const KEY_USAGES = ["sign", "verify"];
+    const INTEGER_ARRAYS = [
+        Int8Array, Int16Array, Int32Array, BigInt64Array,
+        Uint8Array, Uint16Array, Uint32Array, BigUint64Array, Uint8ClampedArray,
+    ];
+
+    const _key = Symbol("key");
+    const _type = Symbol("type");
+    const _extractable = Symbol("extractable");
+    const _algorithm = Symbol("algorithm");
+    // the name of the hash used by sign and verify, independent of the exposed algorithm object
+    const _hash = Symbol("hash");
+    const _usages = Symbol("usages");
+
+    function getRandomValues(array) {
+        const prefix = "Failed to execute 'getRandomValues' on 'Crypto'";
+        webidl.requiredArguments(arguments.length, 1, {prefix});
+
+        if (!ArrayPrototypeFind(INTEGER_ARRAYS, (type) => array instanceof type)) {
+            throw new DOMException(
+                `${prefix}: The provided ArrayBufferView is not an integer array type`,
+                "TypeMismatchError",
+            );
+        }
+        if (array.byteLength > 65536) {
+            throw new DOMException(
+                `${prefix}: The ArrayBufferView's byte length (${array.byteLength}) exceeds the number of bytes of entropy available via this API (65536)`,
+                "QuotaExceededError",
+            );
+        }
+
+        core.opSync("op_crypto_get_random_values", new Uint8Array(array.buffer, array.byteOffset, array.byteLength));
+        return array;
+    }
+
+    function randomUUID() {
+        return core.opSync("op_crypto_random_uuid");
+    }
+
+    // copies the data so it can't be changed while the operation is running
+    function copyBufferSource(data, prefix) {
+        if (ArrayBufferIsView(data)) {
+            return new Uint8Array(ArrayBufferPrototypeSlice(data.buffer, data.byteOffset, data.byteOffset + data.byteLength));
+        }
+        return new Uint8Array(ArrayBufferPrototypeSlice(webidl.converters.ArrayBuffer(data, {prefix, context: "Argument"}), 0));
+    }
+
+    function toArrayBuffer(bytes) {
+        return ArrayBufferPrototypeSlice(bytes.buffer, bytes.byteOffset, bytes.byteOffset + bytes.byteLength);
+    }
+
+    // returns the uppercase name of the algorithm, which can be a string or an object with a name
+    function normalizeAlgorithmName(algorithm) {
+        const name = typeof algorithm === "string" ? algorithm : algorithm?.name;
+        if (name === undefined) {
+            throw new TypeError("Algorithm must be a string or an object with a name");
+        }
+        return StringPrototypeToUpperCase(String(name));
+    }
+
+    function normalizeDigestAlgorithm(algorithm) {
+        const name = normalizeAlgorithmName(algorithm);
+        if (!ArrayPrototypeIncludes(DIGEST_ALGORITHMS, name)) {
+            throw new DOMException(`Unrecognized algorithm name: ${name}`, "NotSupportedError");
+        }
+        return name;
+    }
+
+    function checkHmacKey(key, usage) {
+        if (!(key instanceof CryptoKey)) {
+            throw new TypeError("Key must be a CryptoKey");
+        }
+        if (key[_algorithm].name !== "HMAC") {
+            throw new DOMException("Key algorithm mismatch", "InvalidAccessError");
+        }
+        if (!ArrayPrototypeIncludes(key[_usages], usage)) {
+            throw new DOMException(`Key does not support the '${usage}' operation`, "InvalidAccessError");
+        }
+    }
+
+    class CryptoKey {
+        constructor() {
+            throw new TypeError("Illegal constructor");
+        }
+
+        get type() {
+            return this[_type];
+        }
+
+        get extractable() {
+            return this[_extractable];
+        }
+
+        get algorithm() {
+            return this[_algorithm];
+        }
+
+        get usages() {
+            return this[_usages];
+        }
+
+        get [SymbolToStringTag]() {
+            return "CryptoKey";
+        }
+    }
+
+    function createCryptoKey(type, extractable, algorithm, usages, key) {
+        const cryptoKey = ObjectCreate(CryptoKey.prototype);
+        cryptoKey[_type] = type;
+        cryptoKey[_extractable] = extractable;
+        if (algorithm.hash !== undefined) {
+            ObjectFreeze(algorithm.hash);
+        }
+        cryptoKey[_algorithm] = ObjectFreeze(algorithm);
+        cryptoKey[_usages] = ObjectFreeze([...usages]);
+        cryptoKey[_key] = key;
+        return cryptoKey;
+    }
+
+    class SubtleCrypto {
+        constructor() {
+            throw new TypeError("Illegal constructor");
+        }
+
+        async digest(algorithm, data) {
+            const prefix = "Failed to execute 'digest' on 'SubtleCrypto'";
+            webidl.requiredArguments(arguments.length, 2, {prefix});
+
+            const name = normalizeDigestAlgorithm(algorithm);
+            const result = core.opSync("op_crypto_digest", name, copyBufferSource(data, prefix));
+            return toArrayBuffer(result);
+        }
+
+        // only raw HMAC keys are supported
+        async importKey(format, keyData, algorithm, extractable, usages) {
+            const prefix = "Failed to execute 'importKey' on 'SubtleCrypto'";
+            webidl.requiredArguments(arguments.length, 5, {prefix});
+
+            if (format !== "raw") {
+                throw new DOMException(`Unsupported key format: ${format}`, "NotSupportedError");
+            }
+            if (normalizeAlgorithmName(algorithm) !== "HMAC") {
+                throw new DOMException(`Unrecognized algorithm name: ${algorithm?.name ?? algorithm}`, "NotSupportedError");
+            }
+            if (!ArrayPrototypeEvery(usages, (usage) => ArrayPrototypeIncludes(KEY_USAGES, usage))) {
+                throw new DOMException("Invalid key usages for HMAC", "SyntaxError");
+            }
+
+            const key = copyBufferSource(keyData, prefix);
+            if (key.byteLength === 0) {
+                throw new DOMException("Key length must not be zero", "DataError");
+            }
+
+            // keys are always used with all of their bits, so a length can only confirm the length of the key data
+            const length = key.byteLength * 8;
+            if (algorithm.length !== undefined && algorithm.length !== length) {
+                throw new DOMException(`Key length must be the length of the key data (${length})`, "DataError");
+            }
+
+            const hash = normalizeDigestAlgorithm(algorithm.hash);
+            const cryptoKey = createCryptoKey("secret", !!extractable, {
+                name: "HMAC",
+                hash: {name: hash},
+                length,
+            }, usages, key);
+            cryptoKey[_hash] = hash;
+            return cryptoKey;
+        }
+
+        async sign(algorithm, key, data) {
+            const prefix = "Failed to execute 'sign' on 'SubtleCrypto'";
+            webidl.requiredArguments(arguments.length, 3, {prefix});
+
+            if (normalizeAlgorithmName(algorithm) !== "HMAC") {
+                throw new DOMException(`Unrecognized algorithm name: ${algorithm?.name ?? algorithm}`, "NotSupportedError");
+            }
+            checkHmacKey(key, "sign");
+
+            const signature = core.opSync("op_crypto_sign_hmac", {
+                hash: key[_hash],
+                key: key[_key],
+            }, copyBufferSource(data, prefix));
+            return toArrayBuffer(signature);
+        }
+
+        async verify(algorithm, key, signature, data) {
+            const prefix = "Failed to execute 'verify' on 'SubtleCrypto'";
+            webidl.requiredArguments(arguments.length, 4, {prefix});
+
+            if (normalizeAlgorithmName(algorithm) !== "HMAC") {
+                throw new DOMException(`Unrecognized algorithm name: ${algorithm?.name ?? algorithm}`, "NotSupportedError");
+            }
+            checkHmacKey(key, "verify");
+
+            return core.opSync("op_crypto_verify_hmac", {
+                hash: key[_hash],
+                key: key[_key],
+                signature: copyBufferSource(signature, prefix),
+            }, copyBufferSource(data, prefix));
+        }
+
+        get [SymbolToStringTag]() {
+            return "SubtleCrypto";
+        }
+    }
+
+    const subtle = ObjectCreate(SubtleCrypto.prototype);
+
+    const crypto = {
+        getRandomValues,
+        randomUUID,
+        subtle,
+    };
+
+    ObjectAssign(bootstrap, {
+        crypto: {
+            crypto,
+            CryptoKey,
+            SubtleCrypto,
+        }
+    })
+})(globalThis)
\ No newline at end of file
@@ -0,0 +1,113 @@
+extern crate deno_core;
+
+use deno_core::{Extension, OpState, ZeroCopyBuf, op_sync, include_js_files};
+use deno_core::error::{AnyError, type_error};
+use hmac::{Hmac, Mac, NewMac};
+use rand::RngCore;
+use serde::Deserialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use uuid::Uuid;
+
+pub fn init() -> Extension {
+    Extension::builder()
+        .js(include_js_files!(
+            prefix "isolator:ext/crypto",
+            "00_crypto.js",
+        ))
+        .ops(vec![
+            ("op_crypto_get_random_values", op_sync(op_crypto_get_random_values)),
+            ("op_crypto_random_uuid", op_sync(op_crypto_random_uuid)),
+            ("op_crypto_digest", op_sync(op_crypto_digest)),
+            ("op_crypto_sign_hmac", op_sync(op_crypto_sign_hmac)),
+            ("op_crypto_verify_hmac", op_sync(op_crypto_verify_hmac)),
+        ])
+        .build()
+}
+
+fn op_crypto_get_random_values(
+    _state: &mut OpState,
+    mut data: ZeroCopyBuf,
+    _: (),
+) -> Result<(), AnyError> {
+    rand::thread_rng().fill_bytes(&mut data);
+    Ok(())
+}
+
+fn op_crypto_random_uuid(
+    _state: &mut OpState,
+    _: (),
+    _: (),
+) -> Result<String, AnyError> {
+    Ok(Uuid::new_v4().to_hyphenated().to_string())
+}
+
+// the algorithm names are normalized in JS
+fn op_crypto_digest(
+    _state: &mut OpState,
+    algorithm: String,
+    data: ZeroCopyBuf,
+) -> Result<ZeroCopyBuf, AnyError> {
+    let output = match algorithm.as_str() {
+        "SHA-1" => Sha1::digest(&data).to_vec(),
+        "SHA-256" => Sha256::digest(&data).to_vec(),
+        "SHA-384" => Sha384::digest(&data).to_vec(),
+        "SHA-512" => Sha512::digest(&data).to_vec(),
+        _ => return Err(type_error(format!("Unsupported digest algorithm: {}", algorithm)))
+    };
+    Ok(ZeroCopyBuf::from(output))
+}
+
+fn hmac_sign<M: Mac + NewMac>(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length
+    let mut mac = M::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hmac_verify<M: Mac + NewMac>(key: &[u8], data: &[u8], signature: &[u8]) -> bool {
+    let mut mac = M::new_from_slice(key).unwrap();
+    mac.update(data);
+    // compares in constant time
+    mac.verify(signature).is_ok()
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HmacArgs {
+    hash: String,
+    key: ZeroCopyBuf,
+    // only used for verifying
+    signature: Option<ZeroCopyBuf>,
+}
+
+fn op_crypto_sign_hmac(
+    _state: &mut OpState,
+    args: HmacArgs,
+    data: ZeroCopyBuf,
+) -> Result<ZeroCopyBuf, AnyError> {
+    let signature = match args.hash.as_str() {
+        "SHA-1" => hmac_sign::<Hmac<Sha1>>(&args.key, &data),
+        "SHA-256" => hmac_sign::<Hmac<Sha256>>(&args.key, &data),
+        "SHA-384" => hmac_sign::<Hmac<Sha384>>(&args.key, &data),
+        "SHA-512" => hmac_sign::<Hmac<Sha512>>(&args.key, &data),
+        _ => return Err(type_error(format!("Unsupported hash algorithm: {}", args.hash)))
+    };
+    Ok(ZeroCopyBuf::from(signature))
+}
+
+fn op_crypto_verify_hmac(
+    _state: &mut OpState,
+    args: HmacArgs,
+    data: ZeroCopyBuf,
+) -> Result<bool, AnyError> {
+    let signature = args.signature.ok_or_else(|| type_error("Missing signature"))?;
+    let valid = match args.hash.as_str() {
+        "SHA-1" => hmac_verify::<Hmac<Sha1>>(&args.key, &data, &signature),
+        "SHA-256" => hmac_verify::<Hmac<Sha256>>(&args.key, &data, &signature),
+        "SHA-384" => hmac_verify::<Hmac<Sha384>>(&args.key, &data, &signature),
+        "SHA-512" => hmac_verify::<Hmac<Sha512>>(&args.key, &data, &signature),
+        _ => return Err(type_error(format!("Unsupported hash algorithm: {}", args.hash)))
+    };
+    Ok(valid)
+}
\ No newline at end of file
@@ -0,0 +1,355 @@
+"use strict";
+
+((window) => {
+    const bootstrap = window.__bootstrap;
+    const webidl = bootstrap.webidl;
+    const {DOMException} = bootstrap.domException;
+    const {queueMicrotask} = window;
+    const {
+        ArrayPrototypeFindIndex,
+        ArrayPrototypePush,
+        ArrayPrototypeSlice,
+        ArrayPrototypeSplice,
+        DateNow,
+        FunctionPrototypeCall,
+        ObjectAssign,
+        ObjectCreate,
+        ObjectDefineProperty,
+        String,
+        Symbol,
+        SymbolToStringTag,
+        TypeError,
+    } = bootstrap.primordials;
+
+    const _type = Symbol("type");
+    const _bubbles = Symbol("bubbles");
+    const _cancelable = Symbol("cancelable");
+    const _composed = Symbol("composed");
+    const _target = Symbol("target");
+    const _currentTarget = Symbol("current target");
+    const _eventPhase = Symbol("event phase");
+    const _canceledFlag = Symbol("canceled flag");
+    const _stopPropagationFlag = Symbol("stop propagation flag");
+    const _stopImmediatePropagationFlag = Symbol("stop immediate propagation flag");
+    const _inPassiveListener = Symbol("in passive listener");
+    const _dispatched = Symbol("dispatched");
+    const _timeStamp = Symbol("time stamp");
+    const _detail = Symbol("detail");
+    const _listeners = Symbol("listeners");
+
+    const NONE = 0;
+    const CAPTURING_PHASE = 1;
+    const AT_TARGET = 2;
+    const BUBBLING_PHASE = 3;
+
+    class Event {
+        constructor(type, eventInitDict = {}) {
+            const prefix = "Failed to construct 'Event'";
+            webidl.requiredArguments(arguments.length, 1, {prefix});
+
+            this[_type] = String(type);
+            this[_bubbles] = !!eventInitDict?.bubbles;
+            this[_cancelable] = !!eventInitDict?.cancelable;
+            this[_composed] = !!eventInitDict?.composed;
+            this[_target] = null;
+            this[_currentTarget] = null;
+            this[_eventPhase] = NONE;
+            this[_canceledFlag] = false;
+            this[_stopPropagationFlag] = false;
+            this[_stopImmediatePropagationFlag] = false;
+            this[_inPassiveListener] = false;
+            this[_dispatched] = false;
+            this[_timeStamp] = DateNow();
+        }
+
+        get type() {
+            return this[_type];
+        }
+
+        get target() {
+            return this[_target];
+        }
+
+        get srcElement() {
+            return this[_target];
+        }
+
+        get currentTarget() {
+            return this[_currentTarget];
+        }
+
+        get eventPhase() {
+            return this[_eventPhase];
+        }
+
+        get bubbles() {
+            return this[_bubbles];
+        }
+
+        get cancelable() {
+            return this[_cancelable];
+        }
+
+        get composed() {
+            return this[_composed];
+        }
+
+        get defaultPrevented() {
+            return this[_canceledFlag];
+        }
+
+        get returnValue() {
+            return !this[_canceledFlag];
+        }
+
+        set returnValue(value) {
+            if (!value) {
+                this.preventDefault();
+            }
+        }
+
+        get cancelBubble() {
+            return this[_stopPropagationFlag];
+        }
+
+        set cancelBubble(value) {
+            if (value) {
+                this[_stopPropagationFlag] = true;
+            }
+        }
+
+        // events are only created by scripts, there is nothing the user agent dispatches
+        get isTrusted() {
+            return false;
+        }
+
+        get timeStamp() {
+            return this[_timeStamp];
+        }
+
+        // there is no tree of targets, so the path only contains the target itself
+        composedPath() {
+            return this[_currentTarget] === null ? [] : [this[_currentTarget]];
+        }
+
+        stopPropagation() {
+            this[_stopPropagationFlag] = true;
+        }
+
+        stopImmediatePropagation() {
+            this[_stopPropagationFlag] = true;
+            this[_stopImmediatePropagationFlag] = true;
+        }
+
+        preventDefault() {
+            if (this[_cancelable] && !this[_inPassiveListener]) {
+                this[_canceledFlag] = true;
+            }
+        }
+
+        get [SymbolToStringTag]() {
+            return "Event";
+        }
+    }
+
+    for (const [name, value] of [
+        ["NONE", NONE],
+        ["CAPTURING_PHASE", CAPTURING_PHASE],
+        ["AT_TARGET", AT_TARGET],
+        ["BUBBLING_PHASE", BUBBLING_PHASE],
+    ]) {
+        ObjectDefineProperty(Event, name, {value, enumerable: true});
+        ObjectDefineProperty(Event.prototype, name, {value, enumerable: true});
+    }
+
+    class CustomEvent extends Event {
+        constructor(type, eventInitDict = {}) {
+            const prefix = "Failed to construct 'CustomEvent'";
+            webidl.requiredArguments(arguments.length, 1, {prefix});
+
+            super(type, eventInitDict);
+            this[_detail] = eventInitDict?.detail ?? null;
+        }
+
+        get detail() {
+            return this[_detail];
+        }
+
+        get [SymbolToStringTag]() {
+            return "CustomEvent";
+        }
+    }
+
+    function normalizeListenerOptions(options) {
+        if (typeof options === "boolean" || options === undefined || options === null) {
+            return {capture: !!options, once: false, passive: false, signal: undefined};
+        }
+        return {
+            capture: !!options.capture,
+            once: !!options.once,
+            passive: !!options.passive,
+            signal: options.signal,
+        };
+    }
+
+    // exceptions thrown by listeners don't stop the dispatch and are reported as uncaught errors
+    function reportException(error) {
+        queueMicrotask(() => {
+            throw error;
+        });
+    }
+
+    class EventTarget {
+        constructor() {
+            this[_listeners] = ObjectCreate(null);
+        }
+
+        addEventListener(type, callback, options = undefined) {
+            const prefix = "Failed to execute 'addEventListener' on 'EventTarget'";
+            webidl.requiredArguments(arguments.length, 2, {prefix});
+
+            if (callback === null || callback === undefined) {
+                return;
+            }
+            if (typeof callback !== "function" && typeof callback !== "object") {
+                throw new TypeError(`${prefix}: The callback provided as parameter 2 is not an object.`);
+            }
+
+            type = String(type);
+            const {capture, once, passive, signal} = normalizeListenerOptions(options);
+            if (signal?.aborted) {
+                return;
+            }
+
+            const listeners = (this[_listeners][type] ??= []);
+            const index = ArrayPrototypeFindIndex(
+                listeners,
+                (listener) => listener.callback === callback && listener.capture === capture,
+            );
+            if (index !== -1) {
+                return;
+            }
+
+            const listener = {callback, capture, once, passive, removed: false};
+            ArrayPrototypePush(listeners, listener);
+
+            if (signal) {
+                signal.addEventListener("abort", () => {
+                    this.removeEventListener(type, callback, {capture});
+                });
+            }
+        }
+
+        removeEventListener(type, callback, options = undefined) {
+            const prefix = "Failed to execute 'removeEventListener' on 'EventTarget'";
+            webidl.requiredArguments(arguments.length, 2, {prefix});
+
+            type = String(type);
+            const listeners = this[_listeners][type];
+            if (listeners === undefined || callback === null || callback === undefined) {
+                return;
+            }
+
+            const {capture} = normalizeListenerOptions(options);
+            const index = ArrayPrototypeFindIndex(
+                listeners,
+                (listener) => listener.callback === callback && listener.capture === capture,
+            );
+            if (index !== -1) {
+                // marks the listener so it isn't called by a dispatch that is already running
+                listeners[index].removed = true;
+                ArrayPrototypeSplice(listeners, index, 1);
+            }
+        }
+
+        // the target has no parents, so the event is only dispatched at the target itself
+        dispatchEvent(event) {
+            const prefix = "Failed to execute 'dispatchEvent' on 'EventTarget'";
+            webidl.requiredArguments(arguments.length, 1, {prefix});
+
+            if (!(event instanceof Event)) {
+                throw new TypeError(`${prefix}: parameter 1 is not of type 'Event'.`);
+            }
+            if (event[_dispatched]) {
+                throw new DOMException(`${prefix}: The event is already being dispatched.`, "InvalidStateError");
+            }
+
+            event[_dispatched] = true;
+            event[_target] = this;
+            event[_currentTarget] = this;
+            event[_eventPhase] = AT_TARGET;
+
+            // listeners added during the dispatch are not called
+            const listeners = ArrayPrototypeSlice(this[_listeners][event.type] ?? []);
+            for (const listener of listeners) {
+                if (listener.removed) {
+                    continue;
+                }
+                if (listener.once) {
+                    this.removeEventListener(event.type, listener.callback, {capture: listener.capture});
+                }
+
+                event[_inPassiveListener] = listener.passive;
+                try {
+                    if (typeof listener.callback === "function") {
+                        FunctionPrototypeCall(listener.callback, this, event);
+                    } else if (typeof listener.callback.handleEvent === "function") {
+                        FunctionPrototypeCall(listener.callback.handleEvent, listener.callback, event);
+                    }
+                } catch (error) {
+                    reportException(error);
+                }
+                event[_inPassiveListener] = false;
+
+                if (event[_stopImmediatePropagationFlag]) {
+                    break;
+                }
+            }
+
+            event[_dispatched] = false;
+            event[_currentTarget] = null;
+            event[_eventPhase] = NONE;
+            event[_stopPropagationFlag] = false;
+            event[_stopImmediatePropagationFlag] = false;
+
+            return !event[_canceledFlag];
+        }
+
+        get [SymbolToStringTag]() {
+            return "EventTarget";
+        }
+    }
+
+    // defines an on<type> attribute that registers the handler as a listener
+    function defineEventHandler(prototype, type) {
+        const _handler = Symbol(`on${type}`);
+        ObjectDefineProperty(prototype, `on${type}`, {
+            get() {
+                return this[_handler]?.handler ?? null;
+            },
+            set(value) {
+                if (this[_handler] === undefined) {
+                    this[_handler] = {handler: null};
+                    const wrapper = this[_handler];
+                    this.addEventListener(type, (event) => {
+                        if (typeof wrapper.handler === "function") {
+                            FunctionPrototypeCall(wrapper.handler, this, event);
+                        }
+                    });
+                }
+                this[_handler].handler = typeof value === "function" ? value : null;
+            },
+            enumerable: true,
+            configurable: true,
+        });
+    }
+
+    ObjectAssign(bootstrap, {
+        event: {
+            Event,
+            CustomEvent,
+            EventTarget,
+            defineEventHandler,
+        }
+    })
+})(globalThis)
\ No newline at end of file
@@ -0,0 +1,90 @@
+"use strict";
+
+((window) => {
+    const bootstrap = window.__bootstrap;
+    const {DOMException} = bootstrap.domException;
+    const {Event, EventTarget, defineEventHandler} = bootstrap.event;
+    const {
+        ObjectAssign,
+        Symbol,
+        SymbolToStringTag,
+        TypeError,
+    } = bootstrap.primordials;
+
+    const _aborted = Symbol("aborted");
+    const _reason = Symbol("reason");
+    const _signal = Symbol("signal");
+    const _signalAbort = Symbol("signal abort");
+    const illegalConstructorKey = Symbol("illegal constructor key");
+
+    class AbortSignal extends EventTarget {
+        constructor(key = null) {
+            if (key !== illegalConstructorKey) {
+                throw new TypeError("Illegal constructor");
+            }
+            super();
+            this[_aborted] = false;
+            this[_reason] = undefined;
+        }
+
+        static abort(reason = undefined) {
+            const signal = new AbortSignal(illegalConstructorKey);
+            signal[_signalAbort](reason);
+            return signal;
+        }
+
+        get aborted() {
+            return this[_aborted];
+        }
+
+        get reason() {
+            return this[_reason];
+        }
+
+        throwIfAborted() {
+            if (this[_aborted]) {
+                throw this[_reason];
+            }
+        }
+
+        [_signalAbort](reason = undefined) {
+            if (this[_aborted]) {
+                return;
+            }
+            this[_aborted] = true;
+            this[_reason] = reason === undefined
+                ? new DOMException("The signal has been aborted", "AbortError")
+                : reason;
+            this.dispatchEvent(new Event("abort"));
+        }
+
+        get [SymbolToStringTag]() {
+            return "AbortSignal";
+        }
+    }
+
+    defineEventHandler(AbortSignal.prototype, "abort");
+
+    class AbortController {
+        constructor() {
+            this[_signal] = new AbortSignal(illegalConstructorKey);
+        }
+
+        get signal() {
+            return this[_signal];
+        }
+
+        abort(reason = undefined) {
+            this[_signal][_signalAbort](reason);
+        }
+
+        get [SymbolToStringTag]() {
+            return "AbortController";
+        }
+    }
+
+    ObjectAssign(bootstrap.event, {
+        AbortSignal,
+        AbortController,
+    })
+})(globalThis)
\ No newline at end of file
@@ -0,0 +1,14 @@
+extern crate deno_core;
+
+use deno_core::{Extension, include_js_files};
+
+// events are dispatched synchronously in JS, so there are no ops
+pub fn init() -> Extension {
+    Extension::builder()
+        .js(include_js_files!(
+            prefix "isolator:ext/event",
+            "00_event.js",
+            "01_abort_signal.js",
+        ))
+        .build()
+}
\ No newline at end of file
@@ -0,0 +1,166 @@
+"use strict";
+
+((window) => {
+    const bootstrap = window.__bootstrap;
+    const {
+        HTTP_TOKEN_CODE_POINT_RE,
+        HTTP_WHITESPACE_PREFIX_RE,
+        HTTP_WHITESPACE_SUFFIX_RE,
+    } = bootstrap.infra;
+    const {
+        ArrayPrototypeFilter,
+        ArrayPrototypeFindIndex,
+        ArrayPrototypeJoin,
+        ArrayPrototypeMap,
+        ArrayPrototypePush,
+        ArrayPrototypeSort,
+        ObjectAssign,
+        ObjectKeys,
+        RegExpPrototypeTest,
+        String,
+        StringPrototypeReplace,
+        StringPrototypeToLowerCase,
+        SafeSet,
+        StringPrototypeIncludes,
+        Symbol,
+        SymbolIterator,
+        SymbolToStringTag,
+        TypeError,
+    } = bootstrap.primordials;
+
+    // list of [lowercase name, value] pairs in insertion order
+    const _list = Symbol("header list");
+
+    function normalizeName(name) {
+        name = String(name);
+        if (!RegExpPrototypeTest(HTTP_TOKEN_CODE_POINT_RE, name)) {
+            throw new TypeError(`Invalid header name: "${name}"`);
+        }
+        return StringPrototypeToLowerCase(name);
+    }
+
+    function normalizeValue(value) {
+        value = String(value);
+        value = StringPrototypeReplace(value, HTTP_WHITESPACE_PREFIX_RE, "");
+        value = StringPrototypeReplace(value, HTTP_WHITESPACE_SUFFIX_RE, "");
+        if (StringPrototypeIncludes(value, "\0") || StringPrototypeIncludes(value, "\r") || StringPrototypeIncludes(value, "\n")) {
+            throw new TypeError(`Invalid header value: "${value}"`);
+        }
+        return value;
+    }
+
+    class Headers {
+        constructor(init = undefined) {
+            this[_list] = [];
+
+            if (init === undefined || init === null) {
+                return;
+            }
+            if (init instanceof Headers) {
+                for (const [name, value] of init[_list]) {
+                    ArrayPrototypePush(this[_list], [name, value]);
+                }
+            } else if (typeof init[SymbolIterator] === "function") {
+                for (const pair of init) {
+                    const entry = [...pair];
+                    if (entry.length !== 2) {
+                        throw new TypeError("Header pairs must contain exactly two items");
+                    }
+                    this.append(entry[0], entry[1]);
+                }
+            } else if (typeof init === "object") {
+                for (const name of ObjectKeys(init)) {
+                    this.append(name, init[name]);
+                }
+            } else {
+                throw new TypeError("Headers must be an object or an iterable of pairs");
+            }
+        }
+
+        append(name, value) {
+            ArrayPrototypePush(this[_list], [normalizeName(name), normalizeValue(value)]);
+        }
+
+        delete(name) {
+            name = normalizeName(name);
+            this[_list] = ArrayPrototypeFilter(this[_list], (entry) => entry[0] !== name);
+        }
+
+        get(name) {
+            name = normalizeName(name);
+            const values = ArrayPrototypeMap(
+                ArrayPrototypeFilter(this[_list], (entry) => entry[0] === name),
+                (entry) => entry[1],
+            );
+            return values.length === 0 ? null : ArrayPrototypeJoin(values, ", ");
+        }
+
+        has(name) {
+            name = normalizeName(name);
+            return ArrayPrototypeFindIndex(this[_list], (entry) => entry[0] === name) !== -1;
+        }
+
+        set(name, value) {
+            name = normalizeName(name);
+            value = normalizeValue(value);
+
+            const index = ArrayPrototypeFindIndex(this[_list], (entry) => entry[0] === name);
+            if (index === -1) {
+                ArrayPrototypePush(this[_list], [name, value]);
+            } else {
+                // the first occurrence is replaced and all others are removed
+                this[_list][index] = [name, value];
+                this[_list] = ArrayPrototypeFilter(
+                    this[_list],
+                    (entry, i) => i <= index || entry[0] !== name,
+                );
+            }
+        }
+
+        forEach(callback, thisArg = undefined) {
+            for (const [name, value] of this.entries()) {
+                callback.call(thisArg, value, name, this);
+            }
+        }
+
+        // sorted by name with the values of the same name combined
+        * entries() {
+            const names = ArrayPrototypeSort([...new SafeSet(ArrayPrototypeMap(this[_list], (entry) => entry[0]))]);
+            for (const name of names) {
+                yield [name, this.get(name)];
+            }
+        }
+
+        * keys() {
+            for (const [name] of this.entries()) {
+                yield name;
+            }
+        }
+
+        * values() {
+            for (const [, value] of this.entries()) {
+                yield value;
+            }
+        }
+
+        [SymbolIterator]() {
+            return this.entries();
+        }
+
+        get [SymbolToStringTag]() {
+            return "Headers";
+        }
+    }
+
+    // the pairs without combining values, used to serialize the headers for resource requests
+    function headerList(headers) {
+        return ArrayPrototypeMap(headers[_list], (entry) => [entry[0], entry[1]]);
+    }
+
+    ObjectAssign(bootstrap, {
+        headers: {
+            Headers,
+            headerList,
+        }
+    })
+})(globalThis)
\ No newline at end of file
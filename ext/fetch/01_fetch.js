@@ -0,0 +1,348 @@
+"use strict";
+
+((window) => {
+    const bootstrap = window.__bootstrap;
+    const core = window.Deno.core;
+    const {Headers, headerList} = bootstrap.headers;
+    const {IsolatorResourceError} = bootstrap.resources;
+    const {URL, URLSearchParams} = bootstrap.url;
+    const {AbortController} = bootstrap.event;
+    const {
+        HTTP_TOKEN_CODE_POINT_RE,
+        forgivingBase64Encode,
+        forgivingBase64Decode,
+    } = bootstrap.infra;
+    const {
+        ArrayBuffer,
+        ArrayBufferIsView,
+        ArrayBufferPrototypeSlice,
+        ArrayPrototypeIncludes,
+        JSONParse,
+        JSONStringify,
+        NumberIsInteger,
+        ObjectAssign,
+        ObjectDefineProperties,
+        ObjectGetOwnPropertyDescriptors,
+        RangeError,
+        RegExpPrototypeTest,
+        String,
+        StringFromCharCode,
+        StringPrototypeCharCodeAt,
+        StringPrototypeToUpperCase,
+        Symbol,
+        SymbolToStringTag,
+        TypeError,
+        TypedArrayPrototypeSubarray,
+        Uint8Array,
+    } = bootstrap.primordials;
+
+    const _body = Symbol("body");
+    const _bodyUsed = Symbol("body used");
+    const _method = Symbol("method");
+    const _url = Symbol("url");
+    const _headers = Symbol("headers");
+    const _signal = Symbol("signal");
+    const _status = Symbol("status");
+    const _statusText = Symbol("status text");
+
+    const NORMALIZED_METHODS = ["DELETE", "GET", "HEAD", "OPTIONS", "POST", "PUT"];
+    const FORBIDDEN_METHODS = ["CONNECT", "TRACE", "TRACK"];
+    const REDIRECT_STATUSES = [301, 302, 303, 307, 308];
+
+    // resource request payloads are byte strings where each character represents one byte
+    function bytesToByteString(bytes) {
+        let result = "";
+        for (let i = 0; i < bytes.length; i += 0x8000) {
+            result += StringFromCharCode(...TypedArrayPrototypeSubarray(bytes, i, i + 0x8000));
+        }
+        return result;
+    }
+
+    function byteStringToBytes(byteString) {
+        const bytes = new Uint8Array(byteString.length);
+        for (let i = 0; i < byteString.length; i++) {
+            bytes[i] = StringPrototypeCharCodeAt(byteString, i);
+        }
+        return bytes;
+    }
+
+    // returns the bytes of the body and the content type it implies
+    function extractBody(body) {
+        if (body instanceof ArrayBuffer) {
+            return {bytes: new Uint8Array(ArrayBufferPrototypeSlice(body, 0)), contentType: null};
+        }
+        if (ArrayBufferIsView(body)) {
+            const bytes = new Uint8Array(body.buffer, body.byteOffset, body.byteLength);
+            return {bytes: new Uint8Array(bytes), contentType: null};
+        }
+        if (body instanceof URLSearchParams) {
+            return {bytes: core.encode(body.toString()), contentType: "application/x-www-form-urlencoded;charset=UTF-8"};
+        }
+        return {bytes: core.encode(String(body)), contentType: "text/plain;charset=UTF-8"};
+    }
+
+    function initBody(target, body, headers) {
+        target[_bodyUsed] = false;
+
+        if (body === undefined || body === null) {
+            target[_body] = null;
+            return;
+        }
+
+        const {bytes, contentType} = extractBody(body);
+        target[_body] = bytes;
+        if (contentType !== null && !headers.has("content-type")) {
+            headers.set("content-type", contentType);
+        }
+    }
+
+    function consumeBody(target) {
+        if (target[_bodyUsed]) {
+            throw new TypeError("Body has already been consumed");
+        }
+        target[_bodyUsed] = true;
+        return target[_body] ?? new Uint8Array(0);
+    }
+
+    // methods shared by Request and Response
+    const bodyMethods = {
+        get bodyUsed() {
+            return this[_bodyUsed];
+        },
+
+        async arrayBuffer() {
+            const bytes = consumeBody(this);
+            return ArrayBufferPrototypeSlice(bytes.buffer, bytes.byteOffset, bytes.byteOffset + bytes.byteLength);
+        },
+
+        async text() {
+            return core.decode(consumeBody(this));
+        },
+
+        async json() {
+            return JSONParse(core.decode(consumeBody(this)));
+        },
+    };
+
+    function normalizeMethod(method) {
+        method = String(method);
+        if (!RegExpPrototypeTest(HTTP_TOKEN_CODE_POINT_RE, method)) {
+            throw new TypeError(`Invalid method: "${method}"`);
+        }
+
+        const upperCase = StringPrototypeToUpperCase(method);
+        if (ArrayPrototypeIncludes(FORBIDDEN_METHODS, upperCase)) {
+            throw new TypeError(`Method is forbidden: "${method}"`);
+        }
+        return ArrayPrototypeIncludes(NORMALIZED_METHODS, upperCase) ? upperCase : method;
+    }
+
+    // only absolute http(s) URLs can be requested, there is no base URL to resolve relative ones against
+    function parseUrl(input) {
+        input = String(input);
+        let url;
+        try {
+            url = new URL(input);
+        } catch (e) {
+            throw new TypeError(`Invalid URL: "${input}"`, {cause: e});
+        }
+
+        if (url.protocol !== "http:" && url.protocol !== "https:") {
+            throw new TypeError(`URL scheme must be "http" or "https": "${url.href}"`);
+        }
+        return url.href;
+    }
+
+    class Request {
+        constructor(input, init = {}) {
+            init = init ?? {};
+            let body = init.body;
+
+            if (input instanceof Request) {
+                this[_url] = input.url;
+                this[_method] = init.method !== undefined ? normalizeMethod(init.method) : input.method;
+                this[_headers] = new Headers(init.headers ?? input.headers);
+                this[_signal] = init.signal ?? input.signal;
+                if (body === undefined && input[_body] !== null) {
+                    body = consumeBody(input);
+                }
+            } else {
+                this[_url] = parseUrl(input);
+                this[_method] = normalizeMethod(init.method ?? "GET");
+                this[_headers] = new Headers(init.headers);
+                // requests without a signal get one that is never aborted
+                this[_signal] = init.signal ?? new AbortController().signal;
+            }
+
+            if (body !== undefined && body !== null && (this[_method] === "GET" || this[_method] === "HEAD")) {
+                throw new TypeError("Request with GET/HEAD method cannot have a body");
+            }
+            initBody(this, body, this[_headers]);
+        }
+
+        get method() {
+            return this[_method];
+        }
+
+        get url() {
+            return this[_url];
+        }
+
+        get headers() {
+            return this[_headers];
+        }
+
+        get signal() {
+            return this[_signal];
+        }
+
+        clone() {
+            if (this[_bodyUsed]) {
+                throw new TypeError("Body has already been consumed");
+            }
+            return new Request(this[_url], {
+                method: this[_method],
+                headers: this[_headers],
+                body: this[_body],
+                signal: this[_signal],
+            });
+        }
+
+        get [SymbolToStringTag]() {
+            return "Request";
+        }
+    }
+
+    class Response {
+        constructor(body = null, init = {}) {
+            const status = init.status ?? 200;
+            if (status < 200 || status > 599) {
+                throw new RangeError(`The status provided (${status}) is outside the range [200, 599]`);
+            }
+
+            this[_status] = status;
+            this[_statusText] = String(init.statusText ?? "");
+            this[_headers] = new Headers(init.headers);
+            this[_url] = "";
+            initBody(this, body, this[_headers]);
+        }
+
+        static error() {
+            const response = new Response();
+            response[_status] = 0;
+            return response;
+        }
+
+        static redirect(url, status = 302) {
+            if (!ArrayPrototypeIncludes(REDIRECT_STATUSES, status)) {
+                throw new RangeError(`Invalid redirect status: ${status}`);
+            }
+            return new Response(null, {status, headers: {location: String(url)}});
+        }
+
+        static json(data, init = {}) {
+            const headers = new Headers(init.headers);
+            if (!headers.has("content-type")) {
+                headers.set("content-type", "application/json");
+            }
+            return new Response(JSONStringify(data), {...init, headers});
+        }
+
+        get status() {
+            return this[_status];
+        }
+
+        get statusText() {
+            return this[_statusText];
+        }
+
+        get ok() {
+            return this[_status] >= 200 && this[_status] <= 299;
+        }
+
+        get headers() {
+            return this[_headers];
+        }
+
+        get url() {
+            return this[_url];
+        }
+
+        clone() {
+            if (this[_bodyUsed]) {
+                throw new TypeError("Body has already been consumed");
+            }
+            const response = new Response(this[_body], {
+                statusText: this[_statusText],
+                headers: this[_headers],
+            });
+            response[_status] = this[_status];
+            response[_url] = this[_url];
+            return response;
+        }
+
+        get [SymbolToStringTag]() {
+            return "Response";
+        }
+    }
+
+    for (const target of [Request.prototype, Response.prototype]) {
+        ObjectDefineProperties(target, ObjectGetOwnPropertyDescriptors(bodyMethods));
+    }
+
+    // sends the request as a "fetch" resource request with a JSON envelope
+    async function fetch(input, init = {}) {
+        const request = new Request(input, init);
+        const body = request[_body];
+
+        const envelope = JSONStringify({
+            method: request.method,
+            url: request.url,
+            headers: headerList(request.headers),
+            body: body === null ? null : forgivingBase64Encode(body),
+        });
+
+        let payload;
+        try {
+            payload = await bootstrap.isolator.makeResourceRequestWithResponse("fetch", bytesToByteString(core.encode(envelope)), {
+                signal: request.signal,
+            });
+        } catch (e) {
+            // network errors are reported as TypeError by fetch
+            if (e instanceof IsolatorResourceError) {
+                throw new TypeError(`fetch failed: ${e.message}`, {cause: e});
+            }
+            throw e;
+        }
+
+        let result;
+        try {
+            result = JSONParse(core.decode(byteStringToBytes(payload ?? "")));
+        } catch (e) {
+            throw new TypeError("fetch failed: the response is not a valid fetch envelope", {cause: e});
+        }
+
+        const status = result.status ?? 200;
+        if (!NumberIsInteger(status) || status < 200 || status > 599) {
+            throw new TypeError("fetch failed: the response has an invalid status");
+        }
+
+        const response = new Response(
+            result.body === null || result.body === undefined ? null : forgivingBase64Decode(result.body),
+            {headers: result.headers ?? []},
+        );
+        response[_status] = status;
+        response[_statusText] = String(result.statusText ?? "");
+        response[_url] = request.url;
+        return response;
+    }
+
+    ObjectAssign(bootstrap, {
+        fetch: {
+            fetch,
+            Request,
+            Response,
+            Headers,
+        }
+    })
+})(globalThis)
\ No newline at end of file
@@ -0,0 +1,14 @@
+extern crate deno_core;
+
+use deno_core::{Extension, include_js_files};
+
+// fetch is implemented in JS and sends "fetch" resource requests, so there are no ops
+pub fn init() -> Extension {
+    Extension::builder()
+        .js(include_js_files!(
+            prefix "isolator:ext/fetch",
+            "00_headers.js",
+            "01_fetch.js",
+        ))
+        .build()
+}
\ No newline at end of file
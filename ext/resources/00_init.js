@@ -7,8 +7,48 @@
         ObjectAssign
     } = bootstrap.primordials;
 
-    async function makeResourceRequestWithResponse(kind, payload) {
-        const result = await core.opAsync("op_resource_request_response", {kind, payload})
+    class IsolatorResourceError extends Error {
+        constructor(message, code) {
+            super(message);
+            this.name = "IsolatorResourceError";
+            this.code = code;
+        }
+    }
+
+    // options.timeout overrides the default timeout of the isolate in milliseconds, 0 disables the timeout
+    // options.signal is an AbortSignal that stops waiting for the response and rejects with its reason
+    async function makeResourceRequestWithResponse(kind, payload, options = {}) {
+        const {timeout, signal} = options;
+        if (!signal) {
+            const result = await core.opAsync("op_resource_request_response", {kind, payload, timeout})
+            return unwrapResourceResponse(result)
+        }
+
+        signal.throwIfAborted();
+        const cancelRid = core.opSync("op_resource_request_cancel_handle");
+        const onAbort = () => core.tryClose(cancelRid);
+        signal.addEventListener("abort", onAbort);
+
+        let result;
+        try {
+            result = await core.opAsync("op_resource_request_response", {kind, payload, timeout, cancelRid})
+        } catch (e) {
+            // errors of ops don't keep their class, so the reason of the signal is thrown instead
+            if (signal.aborted) {
+                throw signal.reason
+            }
+            throw e
+        } finally {
+            signal.removeEventListener("abort", onAbort);
+            core.tryClose(cancelRid);
+        }
+        return unwrapResourceResponse(result)
+    }
+
+    function unwrapResourceResponse(result) {
+        if (result.error) {
+            throw new IsolatorResourceError(result.error.message, result.error.code)
+        }
         return result.payload
     }
 
@@ -20,6 +60,9 @@
         isolator: {
             makeResourceRequestWithResponse,
             makeResourceRequest
+        },
+        resources: {
+            IsolatorResourceError
         }
     })
 })(globalThis)
\ No newline at end of file
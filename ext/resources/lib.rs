@@ -1,11 +1,14 @@
 extern crate deno_core;
 
-use deno_core::{Extension, OpState, ZeroCopyBuf, op_async, ByteString, include_js_files};
+use deno_core::{Extension, OpState, ZeroCopyBuf, op_async, op_sync, ByteString, include_js_files};
+use deno_core::{CancelFuture, CancelHandle, RcRef, Resource, ResourceId};
 use deno_core::error::{AnyError, generic_error};
 use serde::Deserialize;
 use serde::Serialize;
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 
@@ -13,14 +16,58 @@ pub struct ResourceRequest {
     pub response_sender: Option<oneshot::Sender<ResourceResponse>>,
     pub kind: String,
     pub payload: Option<Vec<u8>>,
+    // overrides the default timeout of the isolate, zero disables the timeout
+    pub timeout: Option<Duration>,
+    // resolves when the script has aborted the request and stopped waiting for the response
+    pub abort_receiver: Option<oneshot::Receiver<()>>,
 }
 
 pub struct ResourceResponse {
     pub payload: Option<Vec<u8>>,
+    // set when the resource request has failed, the payload is ignored in that case
+    pub error: Option<ResourceError>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceError {
+    // machine readable reason of the failure (e.g. NOT_FOUND)
+    pub code: String,
+    pub message: String,
 }
 
 pub type ResourceRequestSender = Option<mpsc::Sender<ResourceRequest>>;
 
+// implemented by the embedder to count the resource requests and enforce a limit
+pub trait ResourceRequestLimiter {
+    // called before every resource request, an error prevents the request from being sent
+    fn register_resource_request(&self) -> Result<(), AnyError>;
+}
+
+pub type SharedResourceRequestLimiter = Option<Arc<dyn ResourceRequestLimiter>>;
+
+// must be called before sending any resource request so it's counted towards the limit
+pub fn check_resource_request(state: &OpState) -> Result<(), AnyError> {
+    if let Some(limiter) = state.borrow::<SharedResourceRequestLimiter>() {
+        limiter.register_resource_request()
+    } else {
+        Ok(())
+    }
+}
+
+// closing the resource aborts the resource request it has been passed to
+pub struct ResourceRequestCancelHandle(CancelHandle);
+
+impl Resource for ResourceRequestCancelHandle {
+    fn name(&self) -> std::borrow::Cow<'_, str> {
+        "resourceRequestCancelHandle".into()
+    }
+
+    fn close(self: Rc<Self>) {
+        self.0.cancel()
+    }
+}
+
 pub fn init() -> Extension {
     Extension::builder()
         .js(include_js_files!(
@@ -30,9 +77,11 @@ pub fn init() -> Extension {
         .ops(vec![
             ("op_resource_request_response", op_async(op_resource_request_response)),
             ("op_resource_request", op_async(op_resource_request)),
+            ("op_resource_request_cancel_handle", op_sync(op_resource_request_cancel_handle)),
         ])
         .state(|state| {
             state.put(ResourceRequestSender::None);
+            state.put(SharedResourceRequestLimiter::None);
             Ok(())
         })
         .build()
@@ -43,12 +92,17 @@ pub fn init() -> Extension {
 pub struct OpResourceRequestArgs {
     kind: String,
     payload: Option<ByteString>,
+    // milliseconds, only used for requests that wait for a response
+    timeout: Option<u64>,
+    // resource id of a ResourceRequestCancelHandle, only used for requests that wait for a response
+    cancel_rid: Option<ResourceId>,
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OpResourceRequestResponse {
     pub payload: Option<ByteString>,
+    pub error: Option<ResourceError>,
 }
 
 pub async fn op_resource_request_response(
@@ -56,41 +110,80 @@ pub async fn op_resource_request_response(
     args: OpResourceRequestArgs,
     _data: Option<ZeroCopyBuf>) -> Result<OpResourceRequestResponse, AnyError> {
     let (resp_sender, resp_receiver) = oneshot::channel::<ResourceResponse>();
+    let (abort_sender, abort_receiver) = oneshot::channel::<()>();
 
-    {
-        let mut borrowed_state = state.borrow_mut();
-
-        let req_sender = borrowed_state.borrow_mut::<ResourceRequestSender>();
-        if let Some(req_sender) = req_sender {
-            let res = req_sender.send(ResourceRequest {
-                response_sender: Some(resp_sender),
-                kind: args.kind,
-                payload: match args.payload {
-                    Some(p) => Some(p.to_vec()),
-                    None => None
-                },
-            }).await;
-            if let Err(_) = res {
-                return Err(generic_error("Unable to communicate with the request manager"))
-            }
+    // the state is released before waiting for the request manager, other ops might need it in the meantime
+    let (cancel_handle, req_sender) = {
+        let borrowed_state = state.borrow();
+        check_resource_request(&borrowed_state)?;
+
+        let cancel_handle = match args.cancel_rid {
+            Some(rid) => Some(borrowed_state.resource_table.get::<ResourceRequestCancelHandle>(rid)?),
+            None => None
+        };
+
+        (cancel_handle, borrowed_state.borrow::<ResourceRequestSender>().clone())
+    };
+
+    if let Some(req_sender) = req_sender {
+        let res = req_sender.send(ResourceRequest {
+            response_sender: Some(resp_sender),
+            kind: args.kind,
+            payload: match args.payload {
+                Some(p) => Some(p.to_vec()),
+                None => None
+            },
+            timeout: args.timeout.map(Duration::from_millis),
+            abort_receiver: Some(abort_receiver),
+        }).await;
+        if let Err(_) = res {
+            return Err(generic_error("Unable to communicate with the request manager"))
         }
     }
 
-    let resp = resp_receiver.await?;
+    let resp = match cancel_handle {
+        Some(cancel_handle) => {
+            match resp_receiver.or_cancel(RcRef::map(cancel_handle, |r| &r.0)).await {
+                Ok(resp) => resp?,
+                Err(_) => {
+                    // tells the request manager to drop the pending request
+                    let _ = abort_sender.send(());
+                    return Err(generic_error("Resource request has been aborted"))
+                }
+            }
+        }
+        None => resp_receiver.await?
+    };
     Ok(OpResourceRequestResponse {
         payload: match resp.payload {
             Some(p) => Some(ByteString(p)),
             None => None
-        }
+        },
+        // the error is thrown in JS so it can be an IsolatorResourceError
+        error: resp.error,
     })
 }
 
+fn op_resource_request_cancel_handle(
+    state: &mut OpState,
+    _: (),
+    _: (),
+) -> Result<ResourceId, AnyError> {
+    let rid = state.resource_table.add(ResourceRequestCancelHandle(CancelHandle::new()));
+    Ok(rid)
+}
+
 pub async fn op_resource_request(
     state: Rc<RefCell<OpState>>,
     args: OpResourceRequestArgs,
     _data: Option<ZeroCopyBuf>) -> Result<(), AnyError> {
-    let mut borrowed_state = state.borrow_mut();
-    let req_sender = borrowed_state.borrow_mut::<ResourceRequestSender>();
+    // the state is released before waiting for the request manager, other ops might need it in the meantime
+    let req_sender = {
+        let borrowed_state = state.borrow();
+        check_resource_request(&borrowed_state)?;
+        borrowed_state.borrow::<ResourceRequestSender>().clone()
+    };
+
     if let Some(req_sender) = req_sender {
         let res = req_sender.send(ResourceRequest {
             response_sender: None,
@@ -99,6 +192,8 @@ pub async fn op_resource_request(
                 Some(p) => Some(p.to_vec()),
                 None => None
             },
+            timeout: None,
+            abort_receiver: None,
         }).await;
         if let Err(_) = res {
             return Err(generic_error("Unable to communicate with the request manager"))
@@ -4,6 +4,7 @@
 ((window) => {
   const core = window.Deno.core;
   const {
+    ArrayFrom,
     ArrayPrototypeIndexOf,
     ArrayPrototypePush,
     ArrayPrototypeShift,
@@ -15,6 +16,7 @@
     MapPrototypeDelete,
     MapPrototypeGet,
     MapPrototypeHas,
+    MapPrototypeKeys,
     MapPrototypeSet,
     MathMax,
     Number,
@@ -565,6 +567,13 @@
     MapPrototypeDelete(idMap, timer.id);
   }
 
+  // used by the runtime to drop the timers of a script that has been terminated
+  function clearAllTimers() {
+    for (const id of ArrayFrom(MapPrototypeKeys(idMap))) {
+      clearTimer(id);
+    }
+  }
+
   function clearTimeout(id = 0) {
     id >>>= 0;
     if (id === 0) {
@@ -592,6 +601,7 @@
     setTimeout,
     sleep,
     handleTimerMacrotask,
+    clearAllTimers,
     opStopGlobalTimer,
     opStartGlobalTimer,
     opNow,
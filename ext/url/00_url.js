@@ -0,0 +1,306 @@
+"use strict";
+
+((window) => {
+    const bootstrap = window.__bootstrap;
+    const core = window.Deno.core;
+    const webidl = bootstrap.webidl;
+    const {
+        ArrayPrototypeFilter,
+        ArrayPrototypeFind,
+        ArrayPrototypeMap,
+        ArrayPrototypePush,
+        ArrayPrototypeSome,
+        ArrayPrototypeSort,
+        ObjectAssign,
+        ObjectKeys,
+        String,
+        StringPrototypeSlice,
+        Symbol,
+        SymbolIterator,
+        SymbolToStringTag,
+        TypeError,
+    } = bootstrap.primordials;
+
+    const _list = Symbol("list");
+    const _url = Symbol("url");
+    const _parts = Symbol("parts");
+    const _searchParams = Symbol("search params");
+
+    function parse(href, base = undefined) {
+        try {
+            return core.opSync("op_url_parse", href, base);
+        } catch (e) {
+            // errors of ops don't keep their class
+            throw new TypeError(`${e.message}: ${href}`);
+        }
+    }
+
+    class URLSearchParams {
+        constructor(init = "") {
+            this[_list] = [];
+            // the URL the search params belong to, updated when the params change
+            this[_url] = null;
+
+            if (init instanceof URLSearchParams) {
+                this[_list] = ArrayPrototypeMap(init[_list], (pair) => [pair[0], pair[1]]);
+            } else if (typeof init === "object" && init !== null && typeof init[SymbolIterator] === "function") {
+                for (const pair of init) {
+                    const entry = [...pair];
+                    if (entry.length !== 2) {
+                        throw new TypeError("URLSearchParams pairs must contain exactly two items");
+                    }
+                    ArrayPrototypePush(this[_list], [String(entry[0]), String(entry[1])]);
+                }
+            } else if (typeof init === "object" && init !== null) {
+                for (const name of ObjectKeys(init)) {
+                    ArrayPrototypePush(this[_list], [name, String(init[name])]);
+                }
+            } else {
+                init = String(init);
+                if (init[0] === "?") {
+                    init = StringPrototypeSlice(init, 1);
+                }
+                this[_list] = core.opSync("op_url_parse_search_params", init);
+            }
+        }
+
+        #update() {
+            if (this[_url] !== null) {
+                const query = this.toString();
+                this[_url][_parts] = core.opSync("op_url_reparse", this[_url].href, ["search", query]);
+            }
+        }
+
+        append(name, value) {
+            ArrayPrototypePush(this[_list], [String(name), String(value)]);
+            this.#update();
+        }
+
+        delete(name) {
+            name = String(name);
+            this[_list] = ArrayPrototypeFilter(this[_list], (pair) => pair[0] !== name);
+            this.#update();
+        }
+
+        get(name) {
+            name = String(name);
+            const pair = ArrayPrototypeFind(this[_list], (pair) => pair[0] === name);
+            return pair === undefined ? null : pair[1];
+        }
+
+        getAll(name) {
+            name = String(name);
+            return ArrayPrototypeMap(
+                ArrayPrototypeFilter(this[_list], (pair) => pair[0] === name),
+                (pair) => pair[1],
+            );
+        }
+
+        has(name) {
+            name = String(name);
+            return ArrayPrototypeSome(this[_list], (pair) => pair[0] === name);
+        }
+
+        set(name, value) {
+            name = String(name);
+            value = String(value);
+
+            let found = false;
+            // the first occurrence is replaced and all others are removed
+            this[_list] = ArrayPrototypeFilter(this[_list], (pair) => {
+                if (pair[0] !== name) {
+                    return true;
+                }
+                if (found) {
+                    return false;
+                }
+                found = true;
+                pair[1] = value;
+                return true;
+            });
+            if (!found) {
+                ArrayPrototypePush(this[_list], [name, value]);
+            }
+            this.#update();
+        }
+
+        // sorts by name and keeps the order of pairs with the same name
+        sort() {
+            this[_list] = ArrayPrototypeSort(this[_list], (a, b) => a[0] === b[0] ? 0 : a[0] > b[0] ? 1 : -1);
+            this.#update();
+        }
+
+        forEach(callback, thisArg = undefined) {
+            for (const [name, value] of this[_list]) {
+                callback.call(thisArg, value, name, this);
+            }
+        }
+
+        * entries() {
+            for (const [name, value] of this[_list]) {
+                yield [name, value];
+            }
+        }
+
+        * keys() {
+            for (const [name] of this[_list]) {
+                yield name;
+            }
+        }
+
+        * values() {
+            for (const [, value] of this[_list]) {
+                yield value;
+            }
+        }
+
+        [SymbolIterator]() {
+            return this.entries();
+        }
+
+        toString() {
+            return core.opSync("op_url_stringify_search_params", this[_list]);
+        }
+
+        get [SymbolToStringTag]() {
+            return "URLSearchParams";
+        }
+    }
+
+    class URL {
+        constructor(url, base = undefined) {
+            const prefix = "Failed to construct 'URL'";
+            webidl.requiredArguments(arguments.length, 1, {prefix});
+
+            this[_parts] = parse(String(url), base === undefined ? undefined : String(base));
+            this[_searchParams] = null;
+        }
+
+        #set(setter, value) {
+            this[_parts] = core.opSync("op_url_reparse", this[_parts].href, [setter, String(value)]);
+        }
+
+        #updateSearchParams() {
+            if (this[_searchParams] !== null) {
+                this[_searchParams][_list] = core.opSync(
+                    "op_url_parse_search_params",
+                    StringPrototypeSlice(this[_parts].search, 1),
+                );
+            }
+        }
+
+        get href() {
+            return this[_parts].href;
+        }
+
+        set href(value) {
+            this[_parts] = parse(String(value));
+            this.#updateSearchParams();
+        }
+
+        get origin() {
+            return this[_parts].origin;
+        }
+
+        get protocol() {
+            return this[_parts].protocol;
+        }
+
+        set protocol(value) {
+            this.#set("protocol", value);
+        }
+
+        get username() {
+            return this[_parts].username;
+        }
+
+        set username(value) {
+            this.#set("username", value);
+        }
+
+        get password() {
+            return this[_parts].password;
+        }
+
+        set password(value) {
+            this.#set("password", value);
+        }
+
+        get host() {
+            return this[_parts].host;
+        }
+
+        set host(value) {
+            this.#set("host", value);
+        }
+
+        get hostname() {
+            return this[_parts].hostname;
+        }
+
+        set hostname(value) {
+            this.#set("hostname", value);
+        }
+
+        get port() {
+            return this[_parts].port;
+        }
+
+        set port(value) {
+            this.#set("port", value);
+        }
+
+        get pathname() {
+            return this[_parts].pathname;
+        }
+
+        set pathname(value) {
+            this.#set("pathname", value);
+        }
+
+        get search() {
+            return this[_parts].search;
+        }
+
+        set search(value) {
+            this.#set("search", value);
+            this.#updateSearchParams();
+        }
+
+        get hash() {
+            return this[_parts].hash;
+        }
+
+        set hash(value) {
+            this.#set("hash", value);
+        }
+
+        // the params stay linked to the URL, changing one updates the other
+        get searchParams() {
+            if (this[_searchParams] === null) {
+                this[_searchParams] = new URLSearchParams(this[_parts].search);
+                this[_searchParams][_url] = this;
+            }
+            return this[_searchParams];
+        }
+
+        toString() {
+            return this[_parts].href;
+        }
+
+        toJSON() {
+            return this[_parts].href;
+        }
+
+        get [SymbolToStringTag]() {
+            return "URL";
+        }
+    }
+
+    ObjectAssign(bootstrap, {
+        url: {
+            URL,
+            URLSearchParams,
+        }
+    })
+})(globalThis)
\ No newline at end of file
@@ -0,0 +1,139 @@
+extern crate deno_core;
+
+use deno_core::{Extension, OpState, op_sync, include_js_files};
+use deno_core::error::{AnyError, type_error};
+use serde::{Deserialize, Serialize};
+use url::{Url, quirks, form_urlencoded};
+
+pub fn init() -> Extension {
+    Extension::builder()
+        .js(include_js_files!(
+            prefix "isolator:ext/url",
+            "00_url.js",
+        ))
+        .ops(vec![
+            ("op_url_parse", op_sync(op_url_parse)),
+            ("op_url_reparse", op_sync(op_url_reparse)),
+            ("op_url_parse_search_params", op_sync(op_url_parse_search_params)),
+            ("op_url_stringify_search_params", op_sync(op_url_stringify_search_params)),
+        ])
+        .build()
+}
+
+// all components of a URL as they are returned by the getters of the URL class
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlParts {
+    href: String,
+    hash: String,
+    host: String,
+    hostname: String,
+    origin: String,
+    password: String,
+    pathname: String,
+    port: String,
+    protocol: String,
+    search: String,
+    username: String,
+}
+
+impl UrlParts {
+    fn from_url(url: &Url) -> Self {
+        Self {
+            href: quirks::href(url).to_string(),
+            hash: quirks::hash(url).to_string(),
+            host: quirks::host(url).to_string(),
+            hostname: quirks::hostname(url).to_string(),
+            origin: quirks::origin(url),
+            password: quirks::password(url).to_string(),
+            pathname: quirks::pathname(url).to_string(),
+            port: quirks::port(url).to_string(),
+            protocol: quirks::protocol(url).to_string(),
+            search: quirks::search(url).to_string(),
+            username: quirks::username(url).to_string(),
+        }
+    }
+}
+
+fn parse_url(href: &str, base_href: Option<&str>) -> Result<Url, AnyError> {
+    let base_url = match base_href {
+        Some(base_href) => Some(Url::parse(base_href).map_err(|_| type_error("Invalid base URL"))?),
+        None => None
+    };
+
+    Url::options()
+        .base_url(base_url.as_ref())
+        .parse(href)
+        .map_err(|_| type_error("Invalid URL"))
+}
+
+fn op_url_parse(
+    _state: &mut OpState,
+    href: String,
+    base_href: Option<String>,
+) -> Result<UrlParts, AnyError> {
+    let url = parse_url(&href, base_href.as_deref())?;
+    Ok(UrlParts::from_url(&url))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UrlSetter {
+    Hash,
+    Host,
+    Hostname,
+    Password,
+    Pathname,
+    Port,
+    Protocol,
+    Search,
+    Username,
+}
+
+// applies the setter to the URL and returns the updated components
+// invalid values are ignored like in browsers, only the href setter throws
+fn op_url_reparse(
+    _state: &mut OpState,
+    href: String,
+    args: (UrlSetter, String),
+) -> Result<UrlParts, AnyError> {
+    let mut url = parse_url(&href, None)?;
+    let (setter, value) = args;
+
+    match setter {
+        UrlSetter::Hash => quirks::set_hash(&mut url, &value),
+        UrlSetter::Host => { let _ = quirks::set_host(&mut url, &value); }
+        UrlSetter::Hostname => { let _ = quirks::set_hostname(&mut url, &value); }
+        UrlSetter::Password => { let _ = quirks::set_password(&mut url, &value); }
+        UrlSetter::Pathname => quirks::set_pathname(&mut url, &value),
+        UrlSetter::Port => { let _ = quirks::set_port(&mut url, &value); }
+        UrlSetter::Protocol => { let _ = quirks::set_protocol(&mut url, &value); }
+        UrlSetter::Search => quirks::set_search(&mut url, &value),
+        UrlSetter::Username => { let _ = quirks::set_username(&mut url, &value); }
+    }
+
+    Ok(UrlParts::from_url(&url))
+}
+
+// parses an application/x-www-form-urlencoded string without the leading "?"
+fn op_url_parse_search_params(
+    _state: &mut OpState,
+    query: String,
+    _: (),
+) -> Result<Vec<(String, String)>, AnyError> {
+    let params = form_urlencoded::parse(query.as_bytes())
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    Ok(params)
+}
+
+fn op_url_stringify_search_params(
+    _state: &mut OpState,
+    params: Vec<(String, String)>,
+    _: (),
+) -> Result<String, AnyError> {
+    let query = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish();
+    Ok(query)
+}
\ No newline at end of file
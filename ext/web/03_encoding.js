@@ -0,0 +1,111 @@
+"use strict";
+
+((window) => {
+    const core = window.Deno.core;
+    const webidl = window.__bootstrap.webidl;
+    const {
+        ArrayBufferIsView,
+        RangeError,
+        Symbol,
+        SymbolToStringTag,
+        TypeError,
+        Uint8Array,
+    } = window.__bootstrap.primordials;
+
+    const _encoding = Symbol("encoding");
+    const _fatal = Symbol("fatal");
+    const _ignoreBOM = Symbol("ignore BOM");
+
+    class TextEncoder {
+        get encoding() {
+            return "utf-8";
+        }
+
+        encode(input = "") {
+            input = webidl.converters.USVString(input, {
+                prefix: "Failed to execute 'encode' on 'TextEncoder'",
+                context: "Argument 1",
+            });
+            return new Uint8Array(core.opSync("op_encoding_encode", input));
+        }
+
+        encodeInto(source, destination) {
+            const prefix = "Failed to execute 'encodeInto' on 'TextEncoder'";
+            webidl.requiredArguments(arguments.length, 2, {prefix});
+            source = webidl.converters.USVString(source, {prefix, context: "Argument 1"});
+            if (!(destination instanceof Uint8Array)) {
+                throw new TypeError(`${prefix}: Argument 2 is not an Uint8Array.`);
+            }
+            return core.opSync("op_encoding_encode_into", source, destination);
+        }
+
+        get [SymbolToStringTag]() {
+            return "TextEncoder";
+        }
+    }
+
+    class TextDecoder {
+        constructor(label = "utf-8", options = {}) {
+            const prefix = "Failed to construct 'TextDecoder'";
+            label = webidl.converters.DOMString(label, {prefix, context: "Argument 1"});
+
+            const encoding = core.opSync("op_encoding_normalize_label", label);
+            if (encoding === null) {
+                throw new RangeError(`The encoding label provided ('${label}') is invalid.`);
+            }
+
+            this[_encoding] = encoding;
+            this[_fatal] = !!options.fatal;
+            this[_ignoreBOM] = !!options.ignoreBOM;
+        }
+
+        get encoding() {
+            return this[_encoding];
+        }
+
+        get fatal() {
+            return this[_fatal];
+        }
+
+        get ignoreBOM() {
+            return this[_ignoreBOM];
+        }
+
+        // streaming isn't supported because the decoder doesn't keep any state between calls
+        decode(input = new Uint8Array(), options = {}) {
+            if (options.stream) {
+                throw new TypeError("Failed to execute 'decode' on 'TextDecoder': streaming is not supported.");
+            }
+
+            let data;
+            if (ArrayBufferIsView(input)) {
+                data = new Uint8Array(input.buffer, input.byteOffset, input.byteLength);
+            } else {
+                data = new Uint8Array(webidl.converters.ArrayBuffer(input, {
+                    prefix: "Failed to execute 'decode' on 'TextDecoder'",
+                    context: "Argument 1",
+                }));
+            }
+
+            try {
+                return core.opSync("op_encoding_decode", data, {
+                    label: this[_encoding],
+                    fatal: this[_fatal],
+                    ignoreBom: this[_ignoreBOM],
+                });
+            } catch (e) {
+                // errors of ops don't keep their class
+                throw new TypeError(e.message);
+            }
+        }
+
+        get [SymbolToStringTag]() {
+            return "TextDecoder";
+        }
+    }
+
+    window.__bootstrap.encoding = {
+        TextEncoder,
+        TextDecoder,
+    };
+})(globalThis)
\ No newline at end of file
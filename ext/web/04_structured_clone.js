@@ -0,0 +1,58 @@
+"use strict";
+
+((window) => {
+    const core = window.Deno.core;
+    const {
+        ArrayBuffer,
+        ArrayBufferIsView,
+        StringPrototypeCharCodeAt,
+        TypeError,
+        Uint8Array,
+    } = window.__bootstrap.primordials;
+    const {DOMException} = window.__bootstrap.domException;
+
+    // encodes the value with V8's ValueSerializer, the same format is used for the results of scripts
+    function serialize(value) {
+        try {
+            return core.serialize(value);
+        } catch (e) {
+            if (e instanceof TypeError) {
+                throw new DOMException(e.message, "DataCloneError");
+            }
+            throw e;
+        }
+    }
+
+    // accepts the bytes as a buffer or as a byte string like the payloads of resource requests
+    function deserialize(data) {
+        let bytes;
+        if (typeof data === "string") {
+            bytes = new Uint8Array(data.length);
+            for (let i = 0; i < data.length; i++) {
+                bytes[i] = StringPrototypeCharCodeAt(data, i);
+            }
+        } else if (ArrayBufferIsView(data)) {
+            bytes = new Uint8Array(data.buffer, data.byteOffset, data.byteLength);
+        } else if (data instanceof ArrayBuffer) {
+            bytes = new Uint8Array(data);
+        } else {
+            throw new TypeError("Failed to execute 'deserialize': data must be a string, an ArrayBuffer or an ArrayBufferView");
+        }
+
+        try {
+            return core.deserialize(bytes);
+        } catch (e) {
+            throw new DOMException(`Failed to deserialize value: ${e.message}`, "DataCloneError");
+        }
+    }
+
+    function structuredClone(value) {
+        return core.deserialize(serialize(value));
+    }
+
+    window.__bootstrap.structuredClone = {
+        structuredClone,
+        serialize,
+        deserialize,
+    };
+})(globalThis)
\ No newline at end of file
@@ -3,7 +3,9 @@ extern crate deno_core;
 use deno_core::{Extension, ZeroCopyBuf, op_sync};
 use deno_core::include_js_files;
 use deno_core::OpState;
-use deno_core::error::AnyError;
+use deno_core::error::{AnyError, type_error};
+use encoding_rs::{CoderResult, DecoderResult, Encoding};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 pub fn init() -> Extension {
@@ -13,10 +15,16 @@ pub fn init() -> Extension {
             "00_infra.js",
             "01_web_exception.js",
             "02_base64.js",
+            "03_encoding.js",
+            "04_structured_clone.js",
         ))
         .ops(vec![
             ("op_base64_decode", op_sync(op_base64_decode)),
             ("op_base64_encode", op_sync(op_base64_encode)),
+            ("op_encoding_normalize_label", op_sync(op_encoding_normalize_label)),
+            ("op_encoding_decode", op_sync(op_encoding_decode)),
+            ("op_encoding_encode", op_sync(op_encoding_encode)),
+            ("op_encoding_encode_into", op_sync(op_encoding_encode_into)),
         ])
         .build()
 }
@@ -80,6 +88,99 @@ fn op_base64_encode(
     Ok(out)
 }
 
+// returns the canonical name of the encoding or None if the label is unknown
+fn op_encoding_normalize_label(
+    _state: &mut OpState,
+    label: String,
+    _: (),
+) -> Result<Option<String>, AnyError> {
+    let encoding = Encoding::for_label_no_replacement(label.as_bytes());
+    Ok(encoding.map(|encoding| encoding.name().to_lowercase()))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodeOptions {
+    label: String,
+    fatal: bool,
+    ignore_bom: bool,
+}
+
+fn op_encoding_decode(
+    _state: &mut OpState,
+    data: ZeroCopyBuf,
+    options: DecodeOptions,
+) -> Result<String, AnyError> {
+    let encoding = Encoding::for_label(options.label.as_bytes())
+        .ok_or_else(|| type_error(format!("The encoding label provided ('{}') is invalid.", options.label)))?;
+
+    let mut decoder = if options.ignore_bom {
+        encoding.new_decoder_without_bom_handling()
+    } else {
+        encoding.new_decoder_with_bom_removal()
+    };
+
+    let max_length = decoder
+        .max_utf8_buffer_length(data.len())
+        .ok_or_else(|| type_error("Value too large to decode."))?;
+    let mut output = String::with_capacity(max_length);
+
+    if options.fatal {
+        let (result, _) = decoder.decode_to_string_without_replacement(&data, &mut output, true);
+        match result {
+            DecoderResult::InputEmpty => Ok(output),
+            DecoderResult::Malformed(_, _) => Err(type_error("The encoded data is not valid.")),
+            DecoderResult::OutputFull => Err(type_error("Provided buffer too small.")),
+        }
+    } else {
+        let (result, _, _) = decoder.decode_to_string(&data, &mut output, true);
+        match result {
+            CoderResult::InputEmpty => Ok(output),
+            CoderResult::OutputFull => Err(type_error("Provided buffer too small.")),
+        }
+    }
+}
+
+// TextEncoder always uses UTF-8
+fn op_encoding_encode(
+    _state: &mut OpState,
+    input: String,
+    _: (),
+) -> Result<ZeroCopyBuf, AnyError> {
+    Ok(ZeroCopyBuf::from(input.into_bytes()))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodeIntoResult {
+    // UTF-16 code units of the input that have been encoded
+    read: usize,
+    // bytes that have been written to the buffer
+    written: usize,
+}
+
+fn op_encoding_encode_into(
+    _state: &mut OpState,
+    input: String,
+    mut buffer: ZeroCopyBuf,
+) -> Result<EncodeIntoResult, AnyError> {
+    let mut read = 0;
+    let mut written = 0;
+
+    // only complete characters are written if the buffer is too small
+    for c in input.chars() {
+        let length = c.len_utf8();
+        if written + length > buffer.len() {
+            break;
+        }
+        c.encode_utf8(&mut buffer[written..written + length]);
+        written += length;
+        read += c.len_utf16();
+    }
+
+    Ok(EncodeIntoResult { read, written })
+}
+
 #[derive(Debug)]
 pub struct DomExceptionInvalidCharacterError {
   pub msg: String,
@@ -3,12 +3,22 @@
 ((window) => {
     const bootstrap = window.__bootstrap;
     const {
-        ObjectAssign
+        ObjectAssign,
+        ObjectGetOwnPropertyDescriptor,
+        ObjectGetPrototypeOf,
+        ObjectPrototypeHasOwnProperty,
     } = bootstrap.primordials;
 
     const isolator = bootstrap.isolator;
+    const resources = bootstrap.resources;
+    const url = bootstrap.url;
+    const event = bootstrap.event;
+    const fetch = bootstrap.fetch;
+    const crypto = bootstrap.crypto;
 
     const base64 = bootstrap.base64;
+    const encoding = bootstrap.encoding;
+    const structuredClone = bootstrap.structuredClone;
     const timers = bootstrap.timers;
 
     const core = Deno.core
@@ -16,22 +26,68 @@
 
     const console = new bootstrap.console.Console(text => isolator.makeResourceRequest('console', text));
 
+    // looks up a string data property without running code of the script (getters, proxy traps)
+    function getDataProperty(object, key) {
+        while ((typeof object === "object" || typeof object === "function") && object !== null) {
+            if (core.isProxy(object)) {
+                return undefined;
+            }
+            const descriptor = ObjectGetOwnPropertyDescriptor(object, key);
+            if (descriptor !== undefined) {
+                const isString = ObjectPrototypeHasOwnProperty(descriptor, "value") && typeof descriptor.value === "string";
+                return isString ? descriptor.value : undefined;
+            }
+            object = ObjectGetPrototypeOf(object);
+        }
+        return undefined;
+    }
+
+    // the runtime reads the name and message of exceptions with this, they can be thrown values of any kind
+    function exceptionNameAndMessage(exception) {
+        return [getDataProperty(exception, "name"), getDataProperty(exception, "message")];
+    }
+
     function __bootstrapRuntime() {
         if (hasBootstrapped) return;
 
         core.setMacrotaskCallback(timers.handleTimerMacrotask);
+        // makes the structured stack frames available to the runtime
+        Error.prepareStackTrace = core.createPrepareStackTrace();
 
         delete window.Deno;
         delete window.__bootstrap;
+        delete window.__bootstrapRuntime;
         // https://github.com/denoland/deno/issues/4324
         delete Object.prototype.__proto__;
 
         hasBootstrapped = true
+
+        // kept by the runtime, scripts can't reach them
+        return {
+            clearAllTimers: timers.clearAllTimers,
+            exceptionNameAndMessage,
+        };
     }
 
+    // the ValueSerializer format of the results of scripts, used to pass rich values to and from the client
+    ObjectAssign(isolator, {
+        serialize: structuredClone.serialize,
+        deserialize: structuredClone.deserialize,
+    });
+
     const globalScope = {
         atob: base64.atob,
         btoa: base64.btoa,
+        TextEncoder: encoding.TextEncoder,
+        TextDecoder: encoding.TextDecoder,
+        structuredClone: structuredClone.structuredClone,
+        URL: url.URL,
+        URLSearchParams: url.URLSearchParams,
+        Event: event.Event,
+        CustomEvent: event.CustomEvent,
+        EventTarget: event.EventTarget,
+        AbortController: event.AbortController,
+        AbortSignal: event.AbortSignal,
 
         setTimeout: timers.setTimeout,
         clearTimeout: timers.clearTimeout,
@@ -40,7 +96,17 @@
         sleep: timers.sleep,
         console,
 
+        fetch: fetch.fetch,
+        Request: fetch.Request,
+        Response: fetch.Response,
+        Headers: fetch.Headers,
+
+        crypto: crypto.crypto,
+        CryptoKey: crypto.CryptoKey,
+        SubtleCrypto: crypto.SubtleCrypto,
+
         Isolator: isolator,
+        IsolatorResourceError: resources.IsolatorResourceError,
         __bootstrapRuntime
     }
 
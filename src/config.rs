@@ -0,0 +1,281 @@
+use std::error::Error;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use serde::Deserialize;
+use structopt::StructOpt;
+
+const DEFAULT_LISTEN_ADDRESS: &str = "127.0.0.1:50051";
+const DEFAULT_THREAD_POOL_SIZE: usize = 100;
+const DEFAULT_THREAD_STACK_SIZE: usize = 100_000;
+const DEFAULT_SOFT_HEAP_LIMIT: usize = 8 << 20;
+const DEFAULT_CPU_WATCHDOG_INTERVAL: u64 = 1;
+const DEFAULT_MAX_QUEUE_SIZE: usize = 100;
+const DEFAULT_MAX_QUEUE_WAIT: u64 = 10_000;
+
+// the options can be set using CLI flags, environment variables and a TOML file (in that order of precedence)
+// all limits are optional, 0 means that there is no limit
+#[derive(StructOpt, Deserialize, Default)]
+#[structopt(name = "isolator")]
+#[serde(default, deny_unknown_fields)]
+struct Options {
+    /// Path to a TOML file containing the configuration
+    #[structopt(long, env = "CONFIG_FILE", parse(from_os_str))]
+    #[serde(skip)]
+    config_file: Option<PathBuf>,
+
+    /// The address the gRPC server listens on
+    #[structopt(long, env = "LISTEN_ADDRESS")]
+    listen_address: Option<SocketAddr>,
+    /// The count of isolates that can run in parallel
+    #[structopt(long, env = "THREAD_POOL_SIZE")]
+    thread_pool_size: Option<usize>,
+    /// The stack size in bytes of each isolate thread
+    #[structopt(long, env = "THREAD_STACK_SIZE")]
+    thread_stack_size: Option<usize>,
+    /// Milliseconds between two checks of the CPU time of all isolates
+    #[structopt(long, env = "CPU_WATCHDOG_INTERVAL")]
+    cpu_watchdog_interval: Option<u64>,
+    /// The count of clients that can wait for a free thread, 0 rejects clients when all threads are busy
+    #[structopt(long, env = "MAX_QUEUE_SIZE")]
+    max_queue_size: Option<usize>,
+    /// Milliseconds a client can wait for a free thread, 0 waits without a limit
+    #[structopt(long, env = "MAX_QUEUE_WAIT")]
+    max_queue_wait: Option<u64>,
+
+    /// Milliseconds of CPU time, used when the client doesn't set a limit
+    #[structopt(long, env = "DEFAULT_CPU_TIME_LIMIT")]
+    default_cpu_time_limit: Option<u64>,
+    /// Milliseconds of execution time, used when the client doesn't set a limit
+    #[structopt(long, env = "DEFAULT_EXECUTION_TIME_LIMIT")]
+    default_execution_time_limit: Option<u64>,
+    /// Count of resource requests, used when the client doesn't set a limit
+    #[structopt(long, env = "DEFAULT_RESOURCE_REQUESTS_LIMIT")]
+    default_resource_requests_limit: Option<u32>,
+    /// Bytes of heap, used when the client doesn't set a limit
+    #[structopt(long, env = "DEFAULT_SOFT_HEAP_LIMIT")]
+    default_soft_heap_limit: Option<usize>,
+    /// Bytes of heap, used when the client doesn't set a limit
+    #[structopt(long, env = "DEFAULT_HARD_HEAP_LIMIT")]
+    default_hard_heap_limit: Option<usize>,
+    /// Bytes of heap, higher heap limits set by the client are lowered to this
+    #[structopt(long, env = "MAX_HEAP_LIMIT")]
+    max_heap_limit: Option<usize>,
+    /// Milliseconds to wait for the response to a resource request, used when the client doesn't set a timeout
+    #[structopt(long, env = "DEFAULT_RESOURCE_REQUEST_TIMEOUT")]
+    default_resource_request_timeout: Option<u64>,
+
+    /// Kinds of resource requests that are handled in-process by the built-in handlers (e.g. clock)
+    #[structopt(long, env = "BUILTIN_RESOURCE_HANDLERS", use_delimiter = true)]
+    builtin_resource_handlers: Option<Vec<String>>,
+}
+
+pub struct Config {
+    pub listen_address: SocketAddr,
+    pub thread_pool_size: usize,
+    pub thread_stack_size: usize,
+    pub cpu_watchdog_interval: Duration,
+    pub max_queue_size: usize,
+    pub max_queue_wait: Option<Duration>,
+
+    pub default_cpu_time_limit: Option<Duration>,
+    pub default_execution_time_limit: Option<Duration>,
+    pub default_resource_requests_limit: Option<u32>,
+    pub default_soft_heap_limit: usize,
+    pub default_hard_heap_limit: Option<usize>,
+    pub max_heap_limit: Option<usize>,
+    pub default_resource_request_timeout: Option<Duration>,
+
+    pub builtin_resource_handlers: Vec<String>,
+}
+
+fn non_zero<T: Default + PartialEq>(value: Option<T>) -> Option<T> {
+    value.filter(|v| *v != T::default())
+}
+
+impl Config {
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let cli_options = Options::from_args();
+
+        let file_options = match &cli_options.config_file {
+            Some(path) => toml::from_str(&std::fs::read_to_string(path)?)?,
+            None => Options::default()
+        };
+
+        let config = Self::merge(cli_options, file_options);
+        config.validate()?;
+        Ok(config)
+    }
+
+    // unlike the limits these options have no meaning for 0
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.thread_pool_size == 0 {
+            return Err("thread_pool_size must be greater than 0".into());
+        }
+        if self.thread_stack_size == 0 {
+            return Err("thread_stack_size must be greater than 0".into());
+        }
+        if self.cpu_watchdog_interval.is_zero() {
+            return Err("cpu_watchdog_interval must be greater than 0".into());
+        }
+        if self.default_hard_heap_limit.map_or(false, |limit| limit < self.default_soft_heap_limit) {
+            return Err("default_hard_heap_limit must not be lower than default_soft_heap_limit".into());
+        }
+        if let Some(max_heap_limit) = self.max_heap_limit {
+            let default_heap_limit = self.default_hard_heap_limit.unwrap_or(self.default_soft_heap_limit);
+            if default_heap_limit > max_heap_limit {
+                return Err("the default heap limits must not be higher than max_heap_limit".into());
+            }
+        }
+        Ok(())
+    }
+
+    // the heap limits requested by a client, 0 keeps the default and higher values than the maximum are lowered to it
+    pub fn heap_limits(&self, soft_heap_limit: u64, hard_heap_limit: u64) -> Result<(usize, Option<usize>), String> {
+        let clamp = |limit: u64| {
+            let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+            self.max_heap_limit.map_or(limit, |max_heap_limit| limit.min(max_heap_limit))
+        };
+
+        let soft_heap_limit = non_zero(Some(soft_heap_limit))
+            .map(clamp)
+            .unwrap_or(self.default_soft_heap_limit);
+        let hard_heap_limit = non_zero(Some(hard_heap_limit))
+            .map(clamp)
+            .or(self.default_hard_heap_limit);
+
+        if let Some(hard_heap_limit) = hard_heap_limit {
+            if hard_heap_limit < soft_heap_limit {
+                return Err(format!("hard_heap_limit ({}) must not be lower than soft_heap_limit ({})", hard_heap_limit, soft_heap_limit));
+            }
+        }
+        Ok((soft_heap_limit, hard_heap_limit))
+    }
+
+    fn merge(primary: Options, fallback: Options) -> Self {
+        Self {
+            listen_address: primary.listen_address
+                .or(fallback.listen_address)
+                .unwrap_or_else(|| DEFAULT_LISTEN_ADDRESS.parse().unwrap()),
+            thread_pool_size: primary.thread_pool_size
+                .or(fallback.thread_pool_size)
+                .unwrap_or(DEFAULT_THREAD_POOL_SIZE),
+            thread_stack_size: primary.thread_stack_size
+                .or(fallback.thread_stack_size)
+                .unwrap_or(DEFAULT_THREAD_STACK_SIZE),
+            cpu_watchdog_interval: Duration::from_millis(primary.cpu_watchdog_interval
+                .or(fallback.cpu_watchdog_interval)
+                .unwrap_or(DEFAULT_CPU_WATCHDOG_INTERVAL)),
+            max_queue_size: primary.max_queue_size
+                .or(fallback.max_queue_size)
+                .unwrap_or(DEFAULT_MAX_QUEUE_SIZE),
+            max_queue_wait: non_zero(Some(primary.max_queue_wait
+                .or(fallback.max_queue_wait)
+                .unwrap_or(DEFAULT_MAX_QUEUE_WAIT)))
+                .map(Duration::from_millis),
+
+            default_cpu_time_limit: non_zero(primary.default_cpu_time_limit.or(fallback.default_cpu_time_limit))
+                .map(Duration::from_millis),
+            default_execution_time_limit: non_zero(primary.default_execution_time_limit.or(fallback.default_execution_time_limit))
+                .map(Duration::from_millis),
+            default_resource_requests_limit: non_zero(primary.default_resource_requests_limit.or(fallback.default_resource_requests_limit)),
+            default_soft_heap_limit: non_zero(primary.default_soft_heap_limit.or(fallback.default_soft_heap_limit))
+                .unwrap_or(DEFAULT_SOFT_HEAP_LIMIT),
+            default_hard_heap_limit: non_zero(primary.default_hard_heap_limit.or(fallback.default_hard_heap_limit)),
+            max_heap_limit: non_zero(primary.max_heap_limit.or(fallback.max_heap_limit)),
+            default_resource_request_timeout: non_zero(primary.default_resource_request_timeout.or(fallback.default_resource_request_timeout))
+                .map(Duration::from_millis),
+
+            builtin_resource_handlers: primary.builtin_resource_handlers
+                .or(fallback.builtin_resource_handlers)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(options: Options) -> Config {
+        Config::merge(options, Options::default())
+    }
+
+    #[test]
+    fn merge_prefers_primary_options() {
+        let primary = Options::from_iter(vec!["isolator", "--thread-pool-size", "5", "--default-cpu-time-limit", "20"]);
+        let fallback: Options = toml::from_str(r#"
+            thread_pool_size = 10
+            thread_stack_size = 200000
+            default_cpu_time_limit = 30
+            builtin_resource_handlers = ["clock"]
+        "#).unwrap();
+
+        let config = Config::merge(primary, fallback);
+        assert_eq!(config.thread_pool_size, 5);
+        assert_eq!(config.default_cpu_time_limit, Some(Duration::from_millis(20)));
+        // options that are only set in the file
+        assert_eq!(config.thread_stack_size, 200_000);
+        assert_eq!(config.builtin_resource_handlers, vec!["clock".to_string()]);
+        // options that aren't set at all
+        assert_eq!(config.max_queue_size, DEFAULT_MAX_QUEUE_SIZE);
+        assert_eq!(config.default_soft_heap_limit, DEFAULT_SOFT_HEAP_LIMIT);
+    }
+
+    #[test]
+    fn merge_treats_zero_limits_as_unset() {
+        let config = config(Options {
+            max_queue_wait: Some(0),
+            default_execution_time_limit: Some(0),
+            default_soft_heap_limit: Some(0),
+            default_hard_heap_limit: Some(0),
+            ..Default::default()
+        });
+
+        assert_eq!(config.max_queue_wait, None);
+        assert_eq!(config.default_execution_time_limit, None);
+        assert_eq!(config.default_soft_heap_limit, DEFAULT_SOFT_HEAP_LIMIT);
+        assert_eq!(config.default_hard_heap_limit, None);
+    }
+
+    #[test]
+    fn validate_rejects_zero() {
+        assert!(config(Options::default()).validate().is_ok());
+        assert!(config(Options { thread_pool_size: Some(0), ..Default::default() }).validate().is_err());
+        assert!(config(Options { thread_stack_size: Some(0), ..Default::default() }).validate().is_err());
+        assert!(config(Options { cpu_watchdog_interval: Some(0), ..Default::default() }).validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_inconsistent_heap_limits() {
+        let lower_hard_limit = config(Options {
+            default_soft_heap_limit: Some(2000),
+            default_hard_heap_limit: Some(1000),
+            ..Default::default()
+        });
+        assert!(lower_hard_limit.validate().is_err());
+
+        let above_max = config(Options {
+            default_soft_heap_limit: Some(2000),
+            max_heap_limit: Some(1000),
+            ..Default::default()
+        });
+        assert!(above_max.validate().is_err());
+    }
+
+    #[test]
+    fn heap_limits_of_clients() {
+        let config = config(Options {
+            default_soft_heap_limit: Some(1000),
+            max_heap_limit: Some(4000),
+            ..Default::default()
+        });
+
+        assert_eq!(config.heap_limits(0, 0), Ok((1000, None)));
+        assert_eq!(config.heap_limits(2000, 3000), Ok((2000, Some(3000))));
+        assert_eq!(config.heap_limits(u64::MAX, u64::MAX), Ok((4000, Some(4000))));
+        assert!(config.heap_limits(2000, 1500).is_err());
+        // the hard limit of the client is checked against the default soft limit
+        assert!(config.heap_limits(0, 500).is_err());
+    }
+}
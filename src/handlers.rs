@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use ext_resources::ResourceError;
+
+// handles resource requests of a kind in-process instead of forwarding them to the client
+#[tonic::async_trait]
+pub trait ResourceHandler: Send + Sync {
+    // the returned payload is ignored for requests that don't wait for a response
+    async fn handle(&self, payload: Vec<u8>) -> Result<Vec<u8>, ResourceError>;
+}
+
+#[derive(Default)]
+pub struct ResourceHandlers {
+    handlers: HashMap<String, Arc<dyn ResourceHandler>>,
+}
+
+impl ResourceHandlers {
+    // replaces the handler if there already is one for the kind
+    pub fn register(&mut self, kind: &str, handler: Arc<dyn ResourceHandler>) {
+        self.handlers.insert(kind.to_string(), handler);
+    }
+
+    pub fn get(&self, kind: &str) -> Option<Arc<dyn ResourceHandler>> {
+        self.handlers.get(kind).cloned()
+    }
+
+    // creates a registry containing the built-in handlers with the given kinds
+    pub fn builtin(kinds: &[String]) -> Result<Self, Box<dyn Error>> {
+        let mut handlers = Self::default();
+
+        for kind in kinds {
+            match kind.as_str() {
+                "clock" => handlers.register(kind, Arc::new(ClockHandler)),
+                _ => return Err(format!("unknown built-in resource handler: {}", kind).into())
+            }
+        }
+
+        Ok(handlers)
+    }
+}
+
+// responds with the milliseconds since the unix epoch
+pub struct ClockHandler;
+
+#[tonic::async_trait]
+impl ResourceHandler for ClockHandler {
+    async fn handle(&self, _payload: Vec<u8>) -> Result<Vec<u8>, ResourceError> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Ok(now.as_millis().to_string().into_bytes())
+    }
+}
@@ -1,12 +1,14 @@
 use std::collections::HashMap;
 use tonic::transport::Server;
 use std::sync::{Arc, Mutex};
-use std::sync::atomic::AtomicBool;
-use tokio::sync::mpsc;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use tokio::sync::{mpsc, Notify, Semaphore};
 use runtime::SharedRuntimeState;
 use std::thread;
 use crate::manager::{cpu_time_manager, thread_pool_manager};
 use crate::service::IsolatorService;
+use crate::config::Config;
+use crate::handlers::ResourceHandlers;
 
 use service::isolator::isolator_server::IsolatorServer;
 
@@ -14,18 +16,34 @@ mod service;
 mod runtime;
 mod manager;
 mod modules;
+mod config;
+mod handlers;
 
 pub struct GlobalState {
     pub runtimes: Mutex<HashMap<String, Arc<SharedRuntimeState>>>,
-    pub max_thread_count: usize
+    // one permit for each thread in the thread pool
+    pub threads: Arc<Semaphore>,
+    // count of clients that are waiting for a free thread
+    pub queue_size: AtomicUsize,
+    // resource requests of these kinds are handled in-process instead of being sent to the client
+    pub resource_handlers: ResourceHandlers,
+    pub config: Config,
 }
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let addr = config.listen_address;
+    // custom handlers can be registered here
+    let resource_handlers = ResourceHandlers::builtin(&config.builtin_resource_handlers)?;
+
     let (scheduler_sender, scheduler_receiver) = mpsc::channel(1);
     let state = Arc::new(GlobalState {
         runtimes: Mutex::new(HashMap::new()),
-        max_thread_count: 100
+        threads: Arc::new(Semaphore::new(config.thread_pool_size)),
+        queue_size: AtomicUsize::new(0),
+        resource_handlers,
+        config,
     });
 
     let thread_state = state.clone();
@@ -34,16 +52,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let thread_state = state.clone();
     thread::spawn(move || cpu_time_manager(thread_state));
 
+    let shutdown = Arc::new(Notify::new());
     let service = IsolatorService {
         state,
         scheduler: scheduler_sender,
-        accept_requests: AtomicBool::new(true)
+        accept_requests: Arc::new(AtomicBool::new(true)),
+        shutdown: shutdown.clone(),
     };
 
-    let addr = "127.0.0.1:50051".parse().unwrap();
     Server::builder()
         .add_service(IsolatorServer::new(service))
-        .serve(addr)
+        .serve_with_shutdown(addr, async move { shutdown.notified().await })
         .await
         .unwrap();
 
@@ -3,34 +3,66 @@ use std::sync::Arc;
 use crate::GlobalState;
 use std::thread;
 use std::time::Duration;
-use ext_resources::{ResourceRequest, ResourceResponse};
-use tokio::sync::{mpsc, oneshot};
-use crate::runtime::{DefaultScriptContext, ModuleScriptContext, ScriptContext, WrappedRuntime};
+use ext_resources::{ResourceRequest, ResourceResponse, ResourceError};
+use tokio::sync::{mpsc, oneshot, watch, OwnedSemaphorePermit};
+use crate::runtime::{strip_uncaught_prefix, SharedRuntimeState, ScriptException, DefaultScriptContext, InvokeScriptContext, ModuleScriptContext, ScriptContext, ScriptLimits, ScriptUsage, TerminationReason, WrappedRuntime};
 use crate::service::isolator::{
     IsolateScriptResourceRequestMessage,
+    IsolateScriptResourceRequestCancelledMessage,
+    isolate_script_resource_request_cancelled_message::Reason,
     isolate_request,
     isolate_response,
     IsolateScriptDoneMessage,
+    IsolateInitializedMessage,
     isolate_script_done_message,
     schedule_isolate_script_message::{ScriptKind},
-    isolate_script_done_message::{IsolateScriptSuccess, IsolateScriptError},
-    isolate_request::Message::{InitializeMessage, ScriptScheduleMessage, ScriptResourceResponse},
-    isolate_response::Message::{ScriptResourceRequest, ScriptDoneMessage},
+    isolate_script_done_message::{IsolateScriptSuccess, IsolateScriptError, IsolateScriptUsage},
+    isolate_script_done_message::isolate_script_error::{ErrorKind, StackFrame},
+    isolate_request::Message::{InitializeMessage, ScriptScheduleMessage, FunctionInvokeMessage, ScriptResourceResponse, ScriptCancelMessage},
+    isolate_response::Message::{ScriptResourceRequest, ScriptResourceRequestCancelled, ScriptDoneMessage, InitializedMessage},
 };
 use uuid::Uuid;
+use futures::stream::{FuturesUnordered, StreamExt};
+use deno_core::error::{AnyError, JsError};
 
 pub struct ServiceChannelPair {
     pub sender: mpsc::Sender<isolate_response::Message>,
     pub receiver: mpsc::Receiver<isolate_request::Message>,
 }
 
+// everything the runtime thread needs to serve a single AcquireIsolate stream
+pub struct ServiceConnection {
+    pub channels: ServiceChannelPair,
+    // shared with the service so it can kill the isolate and cancel scripts
+    pub runtime_state: Arc<SharedRuntimeState>,
+    // the thread is given back to the wait queue when the permit is dropped
+    pub permit: OwnedSemaphorePermit,
+}
+
 pub struct RuntimeChannelPair {
     pub sender: mpsc::Sender<isolate_request::Message>,
     pub receiver: mpsc::Receiver<isolate_response::Message>,
 }
 
-async fn runtime_messaging_task(mut service_c: ServiceChannelPair, mut runtime_c: RuntimeChannelPair, mut resource_request_c: mpsc::Receiver<ResourceRequest>) {
+async fn runtime_messaging_task(
+    mut service_c: ServiceChannelPair,
+    mut runtime_c: RuntimeChannelPair,
+    mut resource_request_c: mpsc::Receiver<ResourceRequest>,
+    // receives a message from the runtime when a script has been terminated, answered once its requests are dropped
+    mut script_terminated_c: mpsc::Receiver<oneshot::Sender<()>>,
+    mut resource_request_timeout: Option<Duration>,
+    state: Arc<GlobalState>,
+) {
     let mut pending_resource_requests: HashMap<String, oneshot::Sender<ResourceResponse>> = HashMap::new();
+    // resolves to the nonce of the resource request once it has timed out
+    let mut resource_request_timeouts = FuturesUnordered::new();
+    // resolves to the nonce of the resource request once it has been aborted by the script
+    let mut resource_request_aborts = FuturesUnordered::new();
+    // the tasks of the in-process handlers, resolve once they have responded
+    let mut resource_handler_tasks = FuturesUnordered::new();
+    // stops the in-process handlers when the script that made the requests has been terminated
+    // every handler subscribes when it's spawned, so it's stopped even if its task hasn't been polled yet
+    let (resource_handlers_cancel, _) = watch::channel(());
 
     loop {
         tokio::select! {
@@ -38,6 +70,10 @@ async fn runtime_messaging_task(mut service_c: ServiceChannelPair, mut runtime_c
                 if let Some(req) = service_req {
                     match req {
                         InitializeMessage(msg) => {
+                            if msg.resource_request_timeout != 0 {
+                                resource_request_timeout = Some(Duration::from_millis(msg.resource_request_timeout));
+                            }
+
                             let res = runtime_c.sender.send(InitializeMessage(msg)).await;
                             if let Err(_) = res { break; }
                         },
@@ -45,11 +81,23 @@ async fn runtime_messaging_task(mut service_c: ServiceChannelPair, mut runtime_c
                             let res = runtime_c.sender.send(ScriptScheduleMessage(msg)).await;
                             if let Err(_) = res { break; }
                         },
+                        FunctionInvokeMessage(msg) => {
+                            let res = runtime_c.sender.send(FunctionInvokeMessage(msg)).await;
+                            if let Err(_) = res { break; }
+                        },
                         ScriptResourceResponse(msg) => {
                             if let Some(response_sender) = pending_resource_requests.remove(&msg.nonce) {
-                                let _ = response_sender.send(ResourceResponse {payload: Some(msg.payload)});
+                                let _ = response_sender.send(ResourceResponse {
+                                    payload: Some(msg.payload),
+                                    error: msg.error.map(|error| ResourceError {
+                                        code: error.code,
+                                        message: error.message,
+                                    }),
+                                });
                             }
                         }
+                        // scripts are cancelled by the service because CPU intensive wakeups block this task
+                        ScriptCancelMessage(_) => {}
                     }
                 } else {
                     break;
@@ -65,9 +113,71 @@ async fn runtime_messaging_task(mut service_c: ServiceChannelPair, mut runtime_c
             }
             resource_req = resource_request_c.recv() => {
                 if let Some(resource_req) = resource_req {
+                    if let Some(handler) = state.resource_handlers.get(&resource_req.kind) {
+                        let timeout = resource_req.timeout.or(resource_request_timeout)
+                            .filter(|timeout| !timeout.is_zero());
+                        let mut cancel = resource_handlers_cancel.subscribe();
+
+                        // the handler runs in its own task so it doesn't block other messages
+                        resource_handler_tasks.push(tokio::task::spawn_local(async move {
+                            let handle = handler.handle(resource_req.payload.unwrap_or_default());
+                            let timeout = async move {
+                                match timeout {
+                                    Some(timeout) => tokio::time::sleep(timeout).await,
+                                    None => std::future::pending().await
+                                }
+                            };
+                            // the sender is dropped without sending when the request completes normally
+                            let abort_receiver = resource_req.abort_receiver;
+                            let abort = async move {
+                                let aborted = match abort_receiver {
+                                    Some(abort_receiver) => abort_receiver.await.is_ok(),
+                                    None => false
+                                };
+                                if !aborted {
+                                    std::future::pending::<()>().await
+                                }
+                            };
+
+                            let response = tokio::select! {
+                                res = handle => match res {
+                                    Ok(payload) => ResourceResponse { payload: Some(payload), error: None },
+                                    Err(error) => ResourceResponse { payload: None, error: Some(error) },
+                                },
+                                _ = timeout => timed_out_resource_response(),
+                                Ok(_) = cancel.changed() => cancelled_resource_response(),
+                                // nobody is waiting for the response anymore
+                                _ = abort => return,
+                            };
+
+                            if let Some(response_sender) = resource_req.response_sender {
+                                let _ = response_sender.send(response);
+                            }
+                        }));
+                        continue;
+                    }
+
                     let nonce = Uuid::new_v4().to_simple().to_string();
                     if let Some(response_sender) = resource_req.response_sender {
                         pending_resource_requests.insert(nonce.clone(), response_sender);
+
+                        let timeout = resource_req.timeout.or(resource_request_timeout)
+                            .filter(|timeout| !timeout.is_zero());
+                        if let Some(timeout) = timeout {
+                            let nonce = nonce.clone();
+                            resource_request_timeouts.push(async move {
+                                tokio::time::sleep(timeout).await;
+                                nonce
+                            });
+                        }
+
+                        if let Some(abort_receiver) = resource_req.abort_receiver {
+                            let nonce = nonce.clone();
+                            // the sender is dropped without sending when the request completes normally
+                            resource_request_aborts.push(async move {
+                                abort_receiver.await.ok().map(|_| nonce)
+                            });
+                        }
                     }
 
                     let res = service_c.sender.send(ScriptResourceRequest(IsolateScriptResourceRequestMessage {
@@ -80,11 +190,190 @@ async fn runtime_messaging_task(mut service_c: ServiceChannelPair, mut runtime_c
                     break;
                 }
             }
+            Some(done_sender) = script_terminated_c.recv() => {
+                // requests that are still queued have been made by the terminated script as well
+                // because the runtime waits for this task before it starts the next script
+                while let Ok(resource_req) = resource_request_c.try_recv() {
+                    if let Some(response_sender) = resource_req.response_sender {
+                        let _ = response_sender.send(cancelled_resource_response());
+                    }
+                }
+
+                for (nonce, response_sender) in pending_resource_requests.drain() {
+                    let _ = response_sender.send(cancelled_resource_response());
+
+                    let _ = service_c.sender.send(ScriptResourceRequestCancelled(IsolateScriptResourceRequestCancelledMessage {
+                        nonce,
+                        reason: Reason::Cancelled as i32,
+                    })).await;
+                }
+
+                resource_handlers_cancel.send_replace(());
+
+                let _ = done_sender.send(());
+            }
+            Some(nonce) = resource_request_timeouts.next(), if !resource_request_timeouts.is_empty() => {
+                // the client might have responded in the meantime
+                if let Some(response_sender) = pending_resource_requests.remove(&nonce) {
+                    let _ = response_sender.send(timed_out_resource_response());
+
+                    let res = service_c.sender.send(ScriptResourceRequestCancelled(IsolateScriptResourceRequestCancelledMessage {
+                        nonce,
+                        reason: Reason::Timeout as i32,
+                    })).await;
+                    if let Err(_) = res { break; }
+                }
+            }
+            Some(aborted) = resource_request_aborts.next(), if !resource_request_aborts.is_empty() => {
+                if let Some(nonce) = aborted {
+                    // nobody is waiting for the response anymore, so the sender is just dropped
+                    if pending_resource_requests.remove(&nonce).is_some() {
+                        let res = service_c.sender.send(ScriptResourceRequestCancelled(IsolateScriptResourceRequestCancelledMessage {
+                            nonce,
+                            reason: Reason::Aborted as i32,
+                        })).await;
+                        if let Err(_) = res { break; }
+                    }
+                }
+            }
+            Some(_) = resource_handler_tasks.next(), if !resource_handler_tasks.is_empty() => {}
+        }
+    }
+
+    // the handlers must not outlive the connection
+    for task in resource_handler_tasks.iter() {
+        task.abort();
+    }
+}
+
+fn timed_out_resource_response() -> ResourceResponse {
+    ResourceResponse {
+        payload: None,
+        error: Some(ResourceError {
+            code: "TIMEOUT".to_string(),
+            message: "Resource request has timed out".to_string(),
+        }),
+    }
+}
+
+// the response to the requests of a script that has been terminated before they were answered
+fn cancelled_resource_response() -> ResourceResponse {
+    ResourceResponse {
+        payload: None,
+        error: Some(ResourceError {
+            code: "CANCELLED".to_string(),
+            message: "Script has been terminated".to_string(),
+        }),
+    }
+}
+
+fn error_kind(error: &AnyError) -> ErrorKind {
+    match error.downcast_ref::<TerminationReason>() {
+        Some(TerminationReason::CpuTimeExceeded) => ErrorKind::CpuTimeExceeded,
+        Some(TerminationReason::ExecutionTimeExceeded) => ErrorKind::ExecutionTimeExceeded,
+        Some(TerminationReason::HeapLimitExceeded) => ErrorKind::HeapLimitExceeded,
+        Some(TerminationReason::ResourceLimitExceeded) => ErrorKind::ResourceLimitExceeded,
+        Some(TerminationReason::Killed) => ErrorKind::Terminated,
+        Some(TerminationReason::Cancelled) => ErrorKind::Cancelled,
+        None => ErrorKind::Exception
+    }
+}
+
+fn script_error(error: AnyError) -> IsolateScriptError {
+    let mut script_error = IsolateScriptError {
+        kind: error_kind(&error) as i32,
+        text: error.to_string(),
+        ..Default::default()
+    };
+
+    let exception = error.downcast_ref::<ScriptException>();
+    let js_error = exception.map(|exception| &exception.js_error)
+        .or_else(|| error.downcast_ref::<JsError>());
+
+    if let Some(js_error) = js_error {
+        match exception {
+            Some(exception) => {
+                script_error.name = exception.name.clone();
+                script_error.message = exception.message.clone();
+            }
+            // errors that deno_core has created by itself (e.g. for modules) don't have the thrown value anymore
+            None => script_error.message = strip_uncaught_prefix(&js_error.message).to_string()
         }
+        script_error.frames = js_error.frames.iter()
+            .map(|frame| StackFrame {
+                file_name: frame.file_name.clone().unwrap_or_default(),
+                function_name: frame.function_name.clone()
+                    .or_else(|| frame.method_name.clone())
+                    .unwrap_or_default(),
+                line_number: frame.line_number.unwrap_or_default() as u32,
+                column_number: frame.column_number.unwrap_or_default() as u32,
+            })
+            .collect();
+    }
+
+    script_error
+}
+
+fn script_usage(usage: ScriptUsage) -> IsolateScriptUsage {
+    IsolateScriptUsage {
+        cpu_time: usage.cpu_time.as_millis() as u64,
+        execution_time: usage.execution_time.as_millis() as u64,
+        resource_requests_count: usage.resource_requests_count,
+        peak_heap_usage: usage.peak_heap_usage as u64,
+        resource_requests_limit_exceeded: usage.resource_requests_limit_exceeded,
+    }
+}
+
+fn initialize_runtime(runtime: &mut WrappedRuntime, resource_request_sender: &mpsc::Sender<ResourceRequest>) {
+    runtime.create_runtime();
+    runtime.prepare_runtime();
+    runtime.op_state().borrow_mut().put(Some(resource_request_sender.clone()));
+}
+
+// runs the script and returns the message that reports its result
+// scripts that have been cancelled before they were started are not executed
+async fn run_script(
+    runtime: &mut WrappedRuntime,
+    resource_request_sender: &mpsc::Sender<ResourceRequest>,
+    script_terminated_sender: &mpsc::Sender<oneshot::Sender<()>>,
+    nonce: String,
+    script_context: ScriptContext,
+    script_limits: ScriptLimits,
+) -> IsolateScriptDoneMessage {
+    // clients are not required to initialize the isolate before scheduling scripts
+    // this happens before the script is marked as running so a cancel can't terminate the initialization
+    if !runtime.is_created() {
+        initialize_runtime(runtime, resource_request_sender);
+    }
+
+    if !runtime.state.start_script(&nonce) {
+        return IsolateScriptDoneMessage {
+            nonce,
+            result: Some(isolate_script_done_message::Result::Error(script_error(TerminationReason::Cancelled.into()))),
+            usage: Some(script_usage(ScriptUsage::default())),
+        };
+    }
+
+    let (res, usage) = runtime.execute_script(script_context, script_limits, script_terminated_sender).await;
+    runtime.state.finish_script();
+    runtime.cancel_termination();
+
+    let result = match res {
+        Ok(result) => isolate_script_done_message::Result::Success(IsolateScriptSuccess {
+            serialized_data: result.serialized_data,
+            data: result.data,
+        }),
+        Err(e) => isolate_script_done_message::Result::Error(script_error(e)),
+    };
+
+    IsolateScriptDoneMessage {
+        nonce,
+        result: Some(result),
+        usage: Some(script_usage(usage)),
     }
 }
 
-pub fn runtime_manager(state: Arc<GlobalState>, service_c: ServiceChannelPair) {
+pub fn runtime_manager(state: Arc<GlobalState>, connection: ServiceConnection) {
     let mut tokio_runtime = tokio::runtime::Builder::new_current_thread()
         // IO isn't enabled because communication only happens through channels
         .enable_time()
@@ -92,12 +381,16 @@ pub fn runtime_manager(state: Arc<GlobalState>, service_c: ServiceChannelPair) {
         .unwrap();
     let local_set = tokio::task::LocalSet::new();
 
-    let mut runtime = WrappedRuntime::new(state);
-    runtime.create_runtime();
-    runtime.prepare_runtime();
+    let service_c = connection.channels;
+    let resource_request_timeout = state.config.default_resource_request_timeout;
+    let messaging_state = state.clone();
+    let heap_state = state.clone();
+    let _permit = connection.permit;
+
+    let mut runtime = WrappedRuntime::new(state, connection.runtime_state);
 
     let (resource_request_sender, resource_request_receiver) = mpsc::channel::<ext_resources::ResourceRequest>(10);
-    runtime.op_state().borrow_mut().put(Some(resource_request_sender));
+    let (script_terminated_sender, script_terminated_receiver) = mpsc::channel(1);
 
     let (to_sender, to_receiver) = mpsc::channel(10);
     let (from_sender, mut from_receiver) = mpsc::channel(10);
@@ -107,35 +400,47 @@ pub fn runtime_manager(state: Arc<GlobalState>, service_c: ServiceChannelPair) {
     };
 
     local_set.block_on(&mut tokio_runtime, async move {
-        tokio::task::spawn_local(runtime_messaging_task(service_c, runtime_c, resource_request_receiver));
+        tokio::task::spawn_local(runtime_messaging_task(service_c, runtime_c, resource_request_receiver, script_terminated_receiver, resource_request_timeout, messaging_state));
 
         while let Some(req) = from_receiver.recv().await {
             match req {
                 InitializeMessage(msg) => {
-                    let resource_table = &mut *runtime.resource_table();
+                    // limits that are not set by the client keep the configured defaults
+                    {
+                        let resource_table = &mut *runtime.resource_table();
 
-                    if msg.cpu_time_limit == 0 {
-                        resource_table.cpu_time_limit = None
-                    } else {
-                        resource_table.cpu_time_limit = Some(Duration::from_millis(msg.cpu_time_limit))
-                    }
+                        if msg.cpu_time_limit != 0 {
+                            resource_table.cpu_time_limit = Some(Duration::from_millis(msg.cpu_time_limit))
+                        }
+
+                        if msg.execution_time_limit != 0 {
+                            resource_table.execution_time_limit = Some(Duration::from_millis(msg.execution_time_limit))
+                        }
 
-                    if msg.execution_time_limit == 0 {
-                        resource_table.execution_time_limit = None
-                    } else {
-                        resource_table.execution_time_limit = Some(Duration::from_millis(msg.execution_time_limit))
+                        if msg.resource_requests_limit != 0 {
+                            resource_table.resource_requests_limit = Some(msg.resource_requests_limit)
+                        }
                     }
 
-                    if msg.resource_requests_limit == 0 {
-                        resource_table.resource_requests_limit = None
-                    } else {
-                        resource_table.resource_requests_limit = Some(msg.resource_requests_limit)
+                    // the heap limits can only be applied when the isolate is created
+                    if !runtime.is_created() {
+                        // invalid heap limits have already been rejected by the service
+                        if let Ok((soft_heap_limit, hard_heap_limit)) = heap_state.config.heap_limits(msg.soft_heap_limit, msg.hard_heap_limit) {
+                            runtime.soft_heap_limit = soft_heap_limit;
+                            runtime.hard_heap_limit = hard_heap_limit;
+                        }
+                        initialize_runtime(&mut runtime, &resource_request_sender);
                     }
+
+                    let res = to_sender.send(InitializedMessage(IsolateInitializedMessage {
+                        isolate_id: runtime.id.clone()
+                    })).await;
+                    if let Err(_) = res { break; };
                 }
                 ScriptScheduleMessage(msg) => {
                     let script_context = match ScriptKind::from_i32(msg.kind) {
                         Some(ScriptKind::Module) => ScriptContext::Module(ModuleScriptContext {
-                            name: "default".to_string(),
+                            name: if msg.module_name.is_empty() { "default".to_string() } else { msg.module_name },
                             content: msg.content,
                         }),
                         _ => ScriptContext::Default(DefaultScriptContext {
@@ -144,28 +449,33 @@ pub fn runtime_manager(state: Arc<GlobalState>, service_c: ServiceChannelPair) {
                         }),
                     };
 
-                    let res = runtime.execute_script(script_context).await;
-
-                    match res {
-                        Ok(_) => {
-                            let res = to_sender.send(ScriptDoneMessage(IsolateScriptDoneMessage {
-                                nonce: msg.nonce,
-                                result: Some(isolate_script_done_message::Result::Success(IsolateScriptSuccess {
-                                    data: Default::default()
-                                })),
-                            })).await;
-                            if let Err(_) = res { break; };
-                        }
-                        Err(e) => {
-                            let res = to_sender.send(ScriptDoneMessage(IsolateScriptDoneMessage {
-                                nonce: msg.nonce,
-                                result: Some(isolate_script_done_message::Result::Error(IsolateScriptError {
-                                    text: e.to_string()
-                                })),
-                            })).await;
-                            if let Err(_) = res { break; };
-                        }
+                    // 0 means that the script is only limited by the limits of the isolate
+                    let script_limits = ScriptLimits {
+                        cpu_time_limit: Some(msg.cpu_time_limit)
+                            .filter(|limit| *limit != 0)
+                            .map(Duration::from_millis),
+                        execution_time_limit: Some(msg.execution_time_limit)
+                            .filter(|limit| *limit != 0)
+                            .map(Duration::from_millis),
+                        resource_requests_limit: Some(msg.resource_requests_limit)
+                            .filter(|limit| *limit != 0),
                     };
+
+                    let done = run_script(&mut runtime, &resource_request_sender, &script_terminated_sender, msg.nonce, script_context, script_limits).await;
+                    let res = to_sender.send(ScriptDoneMessage(done)).await;
+                    if let Err(_) = res { break; };
+                }
+                FunctionInvokeMessage(msg) => {
+                    let script_context = ScriptContext::Invoke(InvokeScriptContext {
+                        module: Some(msg.module).filter(|module| !module.is_empty()),
+                        export_name: msg.export_name,
+                        args_json: msg.args_json,
+                    });
+
+                    // invocations are only limited by the limits of the isolate
+                    let done = run_script(&mut runtime, &resource_request_sender, &script_terminated_sender, msg.nonce, script_context, ScriptLimits::default()).await;
+                    let res = to_sender.send(ScriptDoneMessage(done)).await;
+                    if let Err(_) = res { break; };
                 }
                 _ => {}
             }
@@ -173,43 +483,45 @@ pub fn runtime_manager(state: Arc<GlobalState>, service_c: ServiceChannelPair) {
     });
 }
 
-pub fn thread_pool_manager(state: Arc<GlobalState>, mut receiver: mpsc::Receiver<ServiceChannelPair>) {
+pub fn thread_pool_manager(state: Arc<GlobalState>, mut receiver: mpsc::Receiver<ServiceConnection>) {
     let pool = threadpool::Builder::new()
-        .num_threads(state.max_thread_count)
-        .thread_stack_size(100_000)
+        .num_threads(state.config.thread_pool_size)
+        .thread_stack_size(state.config.thread_stack_size)
         .build();
 
-    while let Some(service_c) = receiver.blocking_recv() {
+    while let Some(connection) = receiver.blocking_recv() {
         let handle_state = state.clone();
-        pool.execute(move || runtime_manager(handle_state, service_c))
+        pool.execute(move || runtime_manager(handle_state, connection))
     }
 }
 
-// enforces the cpu time across cpu intensive wakeups
+// enforces the cpu time and execution time across cpu intensive wakeups
 pub fn cpu_time_manager(state: Arc<GlobalState>) {
     loop {
-        thread::sleep(Duration::from_millis(1));
+        thread::sleep(state.config.cpu_watchdog_interval);
 
         let mut runtimes_guard = state.runtimes.lock().unwrap();
         let runtimes = &mut *runtimes_guard;
 
         for (_, runtime) in runtimes {
-            let mut resource_table_guard = runtime.resource_table.lock().unwrap();
-            let resource_table = &mut *resource_table_guard;
-
-            if let Some(current_wakeup) = resource_table.current_wakeup {
-                if let Some(cpu_time_limit) = resource_table.cpu_time_limit {
-                    let mut isolate_guard = runtime.isolate_handle.lock().unwrap();
-                    let isolate_handle = &mut *isolate_guard;
-
-                    if let Some(isolate_handle) = isolate_handle {
-                        let cpu_elapsed = resource_table.cpu_time + current_wakeup.elapsed();
-                        if cpu_elapsed > cpu_time_limit {
-                            isolate_handle.terminate_execution();
-                        }
-                    }
+            let termination_reason = {
+                let resource_table = runtime.resource_table.lock().unwrap();
+
+                // waiting scripts are stopped by prepare_wakeup and the timer in execute_script
+                if resource_table.current_wakeup.is_none() {
+                    None
+                } else if resource_table.cpu_time_exceeded() {
+                    Some(TerminationReason::CpuTimeExceeded)
+                } else if resource_table.execution_time_exceeded() {
+                    Some(TerminationReason::ExecutionTimeExceeded)
+                } else {
+                    None
                 }
+            };
+
+            if let Some(termination_reason) = termination_reason {
+                runtime.terminate(termination_reason);
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -3,7 +3,7 @@ use std::pin::Pin;
 use std::rc::Rc;
 use std::cell::RefCell;
 use deno_core::error::{anyhow};
-use ext_resources::{ResourceRequest, ResourceRequestSender, ResourceResponse};
+use ext_resources::{ResourceRequest, ResourceRequestSender, ResourceResponse, check_resource_request};
 use futures::future::FutureExt;
 use tokio::sync::oneshot;
 
@@ -46,23 +46,31 @@ impl ModuleLoader for InternalModuleLoader {
             if let Some(op_state) = op_state {
                 let (resp_sender, resp_receiver) = oneshot::channel::<ResourceResponse>();
 
-                {
-                    let mut borrowed_state = op_state.borrow_mut();
-                    let req_sender = borrowed_state.borrow_mut::<ResourceRequestSender>();
-                    if let Some(req_sender) = req_sender {
-                        let payload = specifier.to_string().as_bytes().to_vec();
-                        let res = req_sender.send(ResourceRequest {
-                            response_sender: Some(resp_sender),
-                            kind: "module".to_string(),
-                            payload: Some(payload),
-                        }).await;
-                        if let Err(_) = res {
-                            return Err(anyhow!("Request Manager unavailable to load module: {}", specifier.to_string()));
-                        }
+                // the state is released before waiting for the request manager, ops might need it in the meantime
+                let req_sender = {
+                    let borrowed_state = op_state.borrow();
+                    check_resource_request(&borrowed_state)?;
+                    borrowed_state.borrow::<ResourceRequestSender>().clone()
+                };
+
+                if let Some(req_sender) = req_sender {
+                    let payload = specifier.to_string().as_bytes().to_vec();
+                    let res = req_sender.send(ResourceRequest {
+                        response_sender: Some(resp_sender),
+                        kind: "module".to_string(),
+                        payload: Some(payload),
+                        timeout: None,
+                        abort_receiver: None,
+                    }).await;
+                    if let Err(_) = res {
+                        return Err(anyhow!("Request Manager unavailable to load module: {}", specifier.to_string()));
                     }
                 }
 
                 let resp = resp_receiver.await?;
+                if let Some(error) = resp.error {
+                    return Err(anyhow!("Failed to load module: {} ({}: {})", specifier.to_string(), error.code, error.message));
+                }
                 if let Some(payload) = resp.payload {
                     if let Ok(payload) = String::from_utf8(payload) {
                         return Ok(ModuleSource {
@@ -1,4 +1,5 @@
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::future::Future;
 use std::mem;
 use std::pin::Pin;
@@ -7,17 +8,173 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
-use deno_core::{JsRuntime, ModuleSpecifier, OpState, RuntimeOptions, Snapshot};
-use deno_core::v8::{CreateParams, IsolateHandle, Global, Value};
-use deno_core::error::{AnyError, generic_error};
+use std::fmt;
+use deno_core::{JsRuntime, ModuleSpecifier, OpState, RuntimeOptions, Snapshot, serde_json};
+use deno_core::v8::{self, CreateParams, IsolateHandle, Global, Value, HeapStatistics, Local, Promise, PromiseState, TryCatch, json};
+use deno_core::v8::ValueSerializerHelper;
+use deno_core::error::{AnyError, JsError, JsStackFrame, generic_error};
 use futures::task::{Waker};
 use futures_util::task::{ArcWake, waker_ref};
 use crate::GlobalState;
+use crate::config::Config;
 use uuid::Uuid;
-use crate::modules::InternalModuleLoader;
+use crate::modules::{InternalModuleLoader, make_module_specifier};
+use tokio::sync::{mpsc, oneshot, Notify};
+use ext_resources::{ResourceRequestLimiter, SharedResourceRequestLimiter};
 
 static RUNTIME_SNAPSHOT: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/RUNTIME_SNAPSHOT.bin"));
-const DEFAULT_SOFT_HEAP_LIMIT: usize = 8 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TerminationReason {
+    CpuTimeExceeded,
+    ExecutionTimeExceeded,
+    HeapLimitExceeded,
+    ResourceLimitExceeded,
+    Killed,
+    Cancelled,
+}
+
+impl fmt::Display for TerminationReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            TerminationReason::CpuTimeExceeded => "Isolate has run out of CPU time",
+            TerminationReason::ExecutionTimeExceeded => "Isolate has run out of execution time",
+            TerminationReason::HeapLimitExceeded => "Isolate has reached its heap limit",
+            TerminationReason::ResourceLimitExceeded => "Isolate has exceeded its resource request limit",
+            TerminationReason::Killed => "Isolate has been killed",
+            TerminationReason::Cancelled => "Script has been cancelled",
+        };
+        f.pad(text)
+    }
+}
+
+impl std::error::Error for TerminationReason {}
+
+// an exception that has been thrown by a script
+// the message of the JsError is already formatted ("Uncaught TypeError: message") and can't be split reliably,
+// so the name and message are read from the thrown value itself
+#[derive(Debug)]
+pub struct ScriptException {
+    pub name: String,
+    pub message: String,
+    pub js_error: JsError,
+}
+
+impl ScriptException {
+    // unlike JsError::from_v8_exception this doesn't run code of the script (getters, proxy traps),
+    // rejected promises are read outside of wakeups where the limits of the script don't apply
+    fn from_v8_exception(scope: &mut v8::HandleScope, exception: Local<Value>, hooks: Option<&RuntimeHooks>) -> Self {
+        let scope = &mut v8::HandleScope::new(scope);
+
+        // the message is created by V8 without side effects
+        let msg = v8::Exception::create_message(scope, exception);
+        let js_message = msg.get(scope).to_rust_string_lossy(scope);
+
+        let mut frames = Vec::new();
+        let stack_trace = v8::Exception::get_stack_trace(scope, exception)
+            .or_else(|| msg.get_stack_trace(scope));
+        if let Some(stack_trace) = stack_trace {
+            for index in 0..stack_trace.get_frame_count() {
+                if let Some(frame) = stack_trace.get_frame(scope, index) {
+                    let file_name = frame.get_script_name_or_source_url(scope)
+                        .map(|name| name.to_rust_string_lossy(scope));
+                    let mut js_frame = JsStackFrame::from_location(file_name, Some(frame.get_line_number() as i64), Some(frame.get_column() as i64));
+                    js_frame.function_name = frame.get_function_name(scope)
+                        .map(|name| name.to_rust_string_lossy(scope))
+                        .filter(|name| !name.is_empty());
+                    js_frame.is_eval = frame.is_eval();
+                    js_frame.is_constructor = frame.is_constructor();
+                    frames.push(js_frame);
+                }
+            }
+        }
+
+        let (name, message) = match hooks {
+            Some(hooks) => hooks.exception_name_and_message(scope, exception),
+            None => (None, None)
+        };
+        let (name, message) = exception_name_and_message(name, message, &js_message);
+
+        let js_error = JsError {
+            stack: Some(format_stack(&js_message, &frames)),
+            script_resource_name: msg.get_script_resource_name(scope)
+                .and_then(|name| Local::<v8::String>::try_from(name).ok())
+                .map(|name| name.to_rust_string_lossy(scope)),
+            source_line: msg.get_source_line(scope)
+                .map(|line| line.to_rust_string_lossy(scope)),
+            line_number: msg.get_line_number(scope).map(|line| line as i64),
+            start_column: Some(msg.get_start_column() as i64),
+            end_column: Some(msg.get_end_column() as i64),
+            message: js_message,
+            frames,
+        };
+
+        Self { name, message, js_error }
+    }
+}
+
+impl fmt::Display for ScriptException {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.js_error.fmt(f)
+    }
+}
+
+impl std::error::Error for ScriptException {}
+
+// formats the stack like V8 does for Error.prototype.stack
+fn format_stack(js_message: &str, frames: &[JsStackFrame]) -> String {
+    let mut stack = strip_uncaught_prefix(js_message).to_string();
+    for frame in frames {
+        let location = format!(
+            "{}:{}:{}",
+            frame.file_name.as_deref().unwrap_or("<anonymous>"),
+            frame.line_number.unwrap_or_default(),
+            frame.column_number.unwrap_or_default(),
+        );
+        match &frame.function_name {
+            Some(function_name) => stack.push_str(&format!("\n    at {} ({})", function_name, location)),
+            None => stack.push_str(&format!("\n    at {}", location)),
+        }
+    }
+    stack
+}
+
+// errors (and objects that look like them) have a name and message,
+// any other thrown value (e.g. a string) only has the message that V8 has created for it
+fn exception_name_and_message(name: Option<String>, message: Option<String>, js_message: &str) -> (String, String) {
+    match (name, message) {
+        (None, None) => (String::new(), strip_uncaught_prefix(js_message).to_string()),
+        (name, message) => (name.unwrap_or_default(), message.unwrap_or_default())
+    }
+}
+
+// removes the prefix that V8 and deno_core add to the messages of uncaught exceptions
+pub fn strip_uncaught_prefix(message: &str) -> &str {
+    let message = match message.strip_prefix("Uncaught") {
+        Some(rest) => rest.strip_prefix(' ').unwrap_or(rest),
+        None => message
+    };
+    message.strip_prefix("(in promise) ").unwrap_or(message)
+}
+
+// limits for a single script, applied in addition to the limits of the isolate
+#[derive(Default, Clone, Copy)]
+pub struct ScriptLimits {
+    pub cpu_time_limit: Option<Duration>,
+    pub execution_time_limit: Option<Duration>,
+    pub resource_requests_limit: Option<u32>,
+}
+
+// what a single script has consumed
+#[derive(Default, Clone, Copy)]
+pub struct ScriptUsage {
+    pub cpu_time: Duration,
+    pub execution_time: Duration,
+    pub resource_requests_count: u32,
+    pub peak_heap_usage: usize,
+    // a resource request has been rejected because of the limit, even if the script has caught the error
+    pub resource_requests_limit_exceeded: bool,
+}
 
 #[derive(Default)]
 pub struct ExecutionResourceTable {
@@ -25,8 +182,12 @@ pub struct ExecutionResourceTable {
     pub cpu_time_limit: Option<Duration>,
     pub resource_requests_limit: Option<u32>,
 
-    // when the execution has started
+    // when the execution of the current script has started
+    // only set while a script scheduled by the client is being executed
     pub started_at: Option<Instant>,
+    // the time spent executing previous scripts
+    // does not include the current script if it's still running (started_at is set)
+    pub execution_time: Duration,
     // when the current poll of the loop started
     // only set when the loop is currently being polled aka the CPU is doing work
     pub current_wakeup: Option<Instant>,
@@ -36,6 +197,103 @@ pub struct ExecutionResourceTable {
 
     // the count of resource requests that have been made
     pub resource_requests_count: u32,
+    // set when a resource request has been rejected during the current script
+    pub resource_requests_limit_exceeded: bool,
+    // why the execution of the current script has been terminated from outside
+    pub termination_reason: Option<TerminationReason>,
+
+    // the bytes of heap that were used at the end of the last wakeup
+    pub heap_usage: usize,
+
+    // the limits of the current script, only set while a script is being executed
+    pub script_limits: ScriptLimits,
+    // the cpu time and resource requests count of the isolate when the current script has started
+    script_cpu_time_offset: Duration,
+    script_resource_requests_offset: u32,
+    // the highest heap usage at the end of a wakeup of the current script
+    script_peak_heap_usage: usize,
+}
+
+impl ExecutionResourceTable {
+    // the cpu time including the current wakeup if there is one
+    pub fn cpu_time_elapsed(&self) -> Duration {
+        match self.current_wakeup {
+            Some(current_wakeup) => self.cpu_time + current_wakeup.elapsed(),
+            None => self.cpu_time
+        }
+    }
+
+    // the execution time including the current script if there is one
+    pub fn execution_time_elapsed(&self) -> Duration {
+        match self.started_at {
+            Some(started_at) => self.execution_time + started_at.elapsed(),
+            None => self.execution_time
+        }
+    }
+
+    pub fn script_usage(&self) -> ScriptUsage {
+        ScriptUsage {
+            cpu_time: self.cpu_time_elapsed().saturating_sub(self.script_cpu_time_offset),
+            execution_time: self.started_at.map(|started_at| started_at.elapsed()).unwrap_or_default(),
+            resource_requests_count: self.resource_requests_count.saturating_sub(self.script_resource_requests_offset),
+            peak_heap_usage: self.script_peak_heap_usage,
+            resource_requests_limit_exceeded: self.resource_requests_limit_exceeded,
+        }
+    }
+
+    pub fn cpu_time_exceeded(&self) -> bool {
+        let isolate_exceeded = self.cpu_time_limit
+            .map_or(false, |limit| self.cpu_time_elapsed() > limit);
+        let script_exceeded = self.script_limits.cpu_time_limit
+            .map_or(false, |limit| self.script_usage().cpu_time > limit);
+
+        isolate_exceeded || script_exceeded
+    }
+
+    pub fn execution_time_exceeded(&self) -> bool {
+        self.execution_time_left() == Some(Duration::ZERO)
+    }
+
+    // the execution time that is left for the current script, None if there is no limit
+    pub fn execution_time_left(&self) -> Option<Duration> {
+        let isolate_left = self.execution_time_limit
+            .map(|limit| limit.saturating_sub(self.execution_time_elapsed()));
+        let script_left = self.script_limits.execution_time_limit
+            .map(|limit| limit.saturating_sub(self.script_usage().execution_time));
+
+        match (isolate_left, script_left) {
+            (Some(isolate_left), Some(script_left)) => Some(isolate_left.min(script_left)),
+            (isolate_left, script_left) => isolate_left.or(script_left)
+        }
+    }
+
+    pub fn resource_requests_limit_reached(&self) -> bool {
+        let isolate_reached = self.resource_requests_limit
+            .map_or(false, |limit| self.resource_requests_count >= limit);
+        let script_reached = self.script_limits.resource_requests_limit
+            .map_or(false, |limit| self.script_usage().resource_requests_count >= limit);
+
+        isolate_reached || script_reached
+    }
+
+    fn start_script(&mut self, limits: ScriptLimits) {
+        self.script_limits = limits;
+        self.script_cpu_time_offset = self.cpu_time;
+        self.script_resource_requests_offset = self.resource_requests_count;
+        self.script_peak_heap_usage = self.heap_usage;
+        self.resource_requests_limit_exceeded = false;
+        self.started_at = Some(Instant::now());
+    }
+
+    fn finish_script(&mut self) -> ScriptUsage {
+        let usage = self.script_usage();
+
+        self.execution_time = self.execution_time.saturating_add(usage.execution_time);
+        self.started_at = None;
+        self.script_limits = ScriptLimits::default();
+
+        usage
+    }
 }
 
 pub struct DefaultScriptContext {
@@ -48,9 +306,36 @@ pub struct ModuleScriptContext {
     pub content: String,
 }
 
+// calls a function without evaluating any code sent by the client
+pub struct InvokeScriptContext {
+    // the specifier of the module that exports the function, None for global functions
+    pub module: Option<String>,
+    pub export_name: String,
+    pub args_json: String,
+}
+
 pub enum ScriptContext {
     Default(DefaultScriptContext),
     Module(ModuleScriptContext),
+    Invoke(InvokeScriptContext),
+}
+
+// the result of a script, serialized while the limits of the script still apply
+pub struct ScriptResult {
+    pub data: String,
+    pub serialized_data: Vec<u8>,
+}
+
+// module names are resolved like imports, names that aren't URLs become https://isolator/<name>
+fn module_specifier(name: &str) -> Result<ModuleSpecifier, AnyError> {
+    Ok(make_module_specifier(name, "https://isolator/")?)
+}
+
+async fn sleep_until(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await
+    }
 }
 
 struct ExecutionPollState {
@@ -103,39 +388,206 @@ impl Future for ExecutionPollFuture {
     }
 }
 
+// the scripts that have been scheduled by the client and not finished yet
+#[derive(Default)]
+pub struct ScheduledScripts {
+    // nonces of scripts that haven't been started yet
+    pending: HashSet<String>,
+    // nonces of pending scripts that have been cancelled and must not be started
+    cancelled: HashSet<String>,
+    // nonce of the script that is currently executed
+    running: Option<String>,
+}
+
+// used to serialize the results of scripts, host objects and shared array buffers aren't supported
+struct ResultSerializer;
+
+impl v8::ValueSerializerImpl for ResultSerializer {
+    fn throw_data_clone_error<'s>(&mut self, scope: &mut v8::HandleScope<'s>, message: Local<'s, v8::String>) {
+        let error = v8::Exception::type_error(scope, message);
+        scope.throw_exception(error);
+    }
+}
+
 // information about the runtime that are SEND
 pub struct SharedRuntimeState {
+    pub created_at: Instant,
     pub resource_table: Mutex<ExecutionResourceTable>,
     pub isolate_handle: Mutex<Option<IsolateHandle>>,
+    // used to close the connection of the isolate from outside
+    pub kill_sender: Mutex<Option<oneshot::Sender<()>>>,
+    pub scripts: Mutex<ScheduledScripts>,
+    // notified when the current execution is terminated
+    pub terminated: Notify,
+}
+
+impl SharedRuntimeState {
+    pub fn new(config: &Config, kill_sender: oneshot::Sender<()>) -> Self {
+        let resource_table = ExecutionResourceTable {
+            cpu_time_limit: config.default_cpu_time_limit,
+            execution_time_limit: config.default_execution_time_limit,
+            resource_requests_limit: config.default_resource_requests_limit,
+            ..Default::default()
+        };
+
+        Self {
+            created_at: Instant::now(),
+            resource_table: Mutex::new(resource_table),
+            isolate_handle: Mutex::new(None),
+            kill_sender: Mutex::new(Some(kill_sender)),
+            scripts: Mutex::new(ScheduledScripts::default()),
+            terminated: Notify::new(),
+        }
+    }
+
+    // terminates the current execution, only the first reason is kept if it's terminated multiple times
+    pub fn terminate(&self, reason: TerminationReason) {
+        self.resource_table.lock().unwrap().termination_reason.get_or_insert(reason);
+
+        if let Some(isolate_handle) = &*self.isolate_handle.lock().unwrap() {
+            isolate_handle.terminate_execution();
+        }
+        self.terminated.notify_waiters();
+    }
+
+    // terminates the current execution and closes the connection
+    // returns false if the isolate has already been killed
+    pub fn kill(&self) -> bool {
+        let kill_sender = self.kill_sender.lock().unwrap().take();
+
+        self.terminate(TerminationReason::Killed);
+
+        if let Some(kill_sender) = kill_sender {
+            let _ = kill_sender.send(());
+            true
+        } else {
+            false
+        }
+    }
+
+    // called when a script is sent to the runtime so it can be cancelled before it has started
+    pub fn schedule_script(&self, nonce: String) {
+        self.scripts.lock().unwrap().pending.insert(nonce);
+    }
+
+    // terminates the script if it's running, otherwise it's dropped when the runtime gets to it
+    // returns false if there is no unfinished script with the nonce
+    pub fn cancel_script(&self, nonce: &str) -> bool {
+        // the lock is held while terminating so the script can't finish in between
+        let scripts = &mut *self.scripts.lock().unwrap();
+
+        if scripts.running.as_deref() == Some(nonce) {
+            self.terminate(TerminationReason::Cancelled);
+            true
+        } else if scripts.pending.remove(nonce) {
+            scripts.cancelled.insert(nonce.to_string());
+            true
+        } else {
+            false
+        }
+    }
+
+    // marks the script as running, returns false if it has been cancelled and must not be started
+    pub fn start_script(&self, nonce: &str) -> bool {
+        let scripts = &mut *self.scripts.lock().unwrap();
+
+        if scripts.cancelled.remove(nonce) {
+            return false;
+        }
+
+        scripts.pending.remove(nonce);
+        scripts.running = Some(nonce.to_string());
+        true
+    }
+
+    pub fn finish_script(&self) {
+        self.scripts.lock().unwrap().running = None;
+    }
+}
+
+impl ResourceRequestLimiter for SharedRuntimeState {
+    fn register_resource_request(&self) -> Result<(), AnyError> {
+        let resource_table = &mut *self.resource_table.lock().unwrap();
+
+        if resource_table.resource_requests_limit_reached() {
+            resource_table.resource_requests_limit_exceeded = true;
+            return Err(TerminationReason::ResourceLimitExceeded.into());
+        }
+
+        resource_table.resource_requests_count += 1;
+        Ok(())
+    }
+}
+
+// functions of the runtime that are returned by __bootstrapRuntime, scripts can't reach them
+struct RuntimeHooks {
+    // drops all timers of the isolate
+    clear_all_timers: Global<v8::Function>,
+    // returns the name and message of a thrown value without running code of the script
+    exception_name_and_message: Global<v8::Function>,
+}
+
+impl RuntimeHooks {
+    fn exception_name_and_message(&self, scope: &mut v8::HandleScope, exception: Local<Value>) -> (Option<String>, Option<String>) {
+        let function = Local::new(scope, &self.exception_name_and_message);
+        let receiver = v8::undefined(scope).into();
+
+        let scope = &mut TryCatch::new(scope);
+        let res = function.call(scope, receiver, &[exception])
+            .and_then(|res| Local::<v8::Array>::try_from(res).ok());
+
+        let mut get_string = |array: Local<v8::Array>, index: u32| array.get_index(scope, index)
+            .filter(|value| value.is_string())
+            .map(|value| value.to_rust_string_lossy(scope));
+
+        match res {
+            Some(array) => (get_string(array, 0), get_string(array, 1)),
+            None => (None, None)
+        }
+    }
+}
+
+fn get_function(scope: &mut v8::HandleScope, object: Local<v8::Object>, name: &str) -> Global<v8::Function> {
+    let key = v8::String::new(scope, name).unwrap();
+    let function = object.get(scope, key.into()).unwrap();
+    Global::new(scope, Local::<v8::Function>::try_from(function).unwrap())
 }
 
 pub struct WrappedRuntime {
     pub id: String,
     pub state: Arc<SharedRuntimeState>,
     global_state: Arc<GlobalState>,
-    soft_heap_limit: usize,
-    hard_heap_limit: Option<usize>,
+    // the heap limits are only applied when the isolate is created
+    pub soft_heap_limit: usize,
+    pub hard_heap_limit: Option<usize>,
 
     runtime: Option<JsRuntime>,
+    // returned by __bootstrapRuntime
+    hooks: Option<RuntimeHooks>,
 }
 
 impl WrappedRuntime {
-    pub fn new(global_state: Arc<GlobalState>) -> Self {
+    pub fn new(global_state: Arc<GlobalState>, state: Arc<SharedRuntimeState>) -> Self {
+        let soft_heap_limit = global_state.config.default_soft_heap_limit;
+        let hard_heap_limit = global_state.config.default_hard_heap_limit;
+
         let res = Self {
             id: Uuid::new_v4().to_simple().to_string(),
-            state: Arc::new(SharedRuntimeState {
-                resource_table: Mutex::new(ExecutionResourceTable::default()),
-                isolate_handle: Mutex::new(None),
-            }),
+            state,
             global_state,
-            soft_heap_limit: DEFAULT_SOFT_HEAP_LIMIT,
-            hard_heap_limit: None,
+            soft_heap_limit,
+            hard_heap_limit,
             runtime: None,
+            hooks: None,
         };
         res.register_globally();
         return res;
     }
 
+    pub fn is_created(&self) -> bool {
+        self.runtime.is_some()
+    }
+
     pub fn resource_table(&self) -> MutexGuard<ExecutionResourceTable> {
         self.state.resource_table.lock().unwrap()
     }
@@ -153,9 +605,13 @@ impl WrappedRuntime {
         let extensions = vec![
             ext_webidl::init(),
             ext_web::init(),
+            ext_event::init(),
             ext_timers::init(),
             ext_resources::init(),
             ext_console::init(),
+            ext_url::init(),
+            ext_fetch::init(),
+            ext_crypto::init(),
         ];
 
         let op_state_cell = Rc::new(RefCell::new(None));
@@ -171,11 +627,17 @@ impl WrappedRuntime {
 
         op_state_cell.replace(Some(runtime.op_state()));
 
+        let limiter: Arc<dyn ResourceRequestLimiter> = self.state.clone();
+        runtime.op_state().borrow_mut().put::<SharedResourceRequestLimiter>(Some(limiter));
+
         let isolate_handle = runtime.v8_isolate().thread_safe_handle();
+        self.state.isolate_handle.lock().unwrap().replace(isolate_handle);
+
+        let state = self.state.clone();
         let hard_heap_limit = self.hard_heap_limit;
         runtime.add_near_heap_limit_callback(move |current: usize, initial: usize| -> usize {
             // soft heap limit reached -> terminate
-            isolate_handle.terminate_execution();
+            state.terminate(TerminationReason::HeapLimitExceeded);
 
             if let Some(hard_limit) = hard_heap_limit {
                 if current >= hard_limit {
@@ -190,34 +652,61 @@ impl WrappedRuntime {
         self.runtime = Some(runtime)
     }
 
+    // a termination can arrive after the script has finished, this keeps it from affecting the next script
+    // must only be called after the script has been marked as finished so it can't be cancelled anymore
+    pub fn cancel_termination(&mut self) {
+        self.resource_table().termination_reason = None;
+        if let Some(runtime) = self.runtime.as_mut() {
+            runtime.v8_isolate().cancel_terminate_execution();
+        }
+    }
+
     pub fn prepare_runtime(&mut self) {
         let runtime = self.runtime.as_mut().unwrap();
-        runtime.execute_script(
+        let hooks = runtime.execute_script(
             "<cleanup>",
             r#"
             __bootstrapRuntime();
-            delete __boostrapRuntime;
         "#,
         ).unwrap();
+
+        let scope = &mut runtime.handle_scope();
+        let hooks = Local::<v8::Object>::try_from(Local::new(scope, hooks)).unwrap();
+        self.hooks = Some(RuntimeHooks {
+            clear_all_timers: get_function(scope, hooks, "clearAllTimers"),
+            exception_name_and_message: get_function(scope, hooks, "exceptionNameAndMessage"),
+        });
+    }
+
+    // the timers of a terminated script would otherwise keep the next scripts waiting for them
+    // must be called after cancel_termination, V8 doesn't run any JS while the execution is terminated
+    pub fn clear_timers(&mut self) {
+        if let (Some(runtime), Some(hooks)) = (self.runtime.as_mut(), self.hooks.as_ref()) {
+            let scope = &mut runtime.handle_scope();
+            let clear_all_timers = Local::new(scope, &hooks.clear_all_timers);
+            let receiver = v8::undefined(scope).into();
+
+            let scope = &mut TryCatch::new(scope);
+            clear_all_timers.call(scope, receiver, &[]);
+        }
     }
 
     fn prepare_wakeup(&self) -> Result<(), AnyError> {
         let resource_table = &mut *self.resource_table();
 
-        if let Some(started_at) = resource_table.started_at {
-            if let Some(execution_time_limit) = resource_table.execution_time_limit {
-                // this avoids starting another wakeup when it has already ran out of execution time
-                if started_at.elapsed() > execution_time_limit {
-                    return Err(generic_error("Isolate has run out of execution time"));
-                }
-            }
+        if let Some(reason) = resource_table.termination_reason {
+            // this avoids starting another wakeup when the script has been terminated while it was waiting
+            return Err(reason.into());
         }
 
-        if let Some(cpu_time_limit) = resource_table.cpu_time_limit {
-            if resource_table.cpu_time > cpu_time_limit {
-                // this avoids starting another wakeup when it has already ran out of cpu time
-                return Err(generic_error("Isolate has run out of CPU time"));
-            }
+        if resource_table.execution_time_exceeded() {
+            // this avoids starting another wakeup when it has already ran out of execution time
+            return Err(TerminationReason::ExecutionTimeExceeded.into());
+        }
+
+        if resource_table.cpu_time_exceeded() {
+            // this avoids starting another wakeup when it has already ran out of cpu time
+            return Err(TerminationReason::CpuTimeExceeded.into());
         }
 
         let new_wakeup = Instant::now();
@@ -227,12 +716,20 @@ impl WrappedRuntime {
     }
 
     fn cleanup_wakeup(&mut self) {
+        let mut heap_statistics = HeapStatistics::default();
+        self.runtime.as_mut().unwrap().v8_isolate().get_heap_statistics(&mut heap_statistics);
+
         let resource_table = &mut self.resource_table();
 
         if let Some(current_wakeup) = resource_table.current_wakeup {
             resource_table.cpu_time = resource_table.cpu_time.saturating_add(current_wakeup.elapsed());
             resource_table.current_wakeup = None
         }
+
+        resource_table.heap_usage = heap_statistics.used_heap_size();
+        if resource_table.started_at.is_some() {
+            resource_table.script_peak_heap_usage = resource_table.script_peak_heap_usage.max(resource_table.heap_usage);
+        }
     }
 
     async fn poll_and_wait(&mut self) -> Option<Result<(), AnyError>> {
@@ -264,65 +761,295 @@ impl WrappedRuntime {
         None
     }
 
-    async fn drive_execution(&mut self, script_context: ScriptContext) -> Result<Option<Global<Value>>, AnyError> {
-        match script_context {
-            ScriptContext::Default(script) => {
-                self.prepare_wakeup()?;
+    async fn run_event_loop(&mut self) -> Result<(), AnyError> {
+        loop {
+            if let Some(result) = self.poll_and_wait().await {
+                break result;
+            }
+        }
+    }
 
-                let runtime = self.runtime.as_mut().unwrap();
-                let res = runtime.execute_script("", script.content.as_str());
+    fn execute_classic_script(&mut self, name: &str, content: &str) -> Result<Global<Value>, AnyError> {
+        self.prepare_wakeup()?;
+
+        // same as JsRuntime::execute_script but keeps the thrown value to create a ScriptException from it
+        let res = {
+            let runtime = self.runtime.as_mut().unwrap();
+            let hooks = self.hooks.as_ref();
+            let scope = &mut runtime.handle_scope();
+
+            let source = v8::String::new(scope, content).unwrap();
+            let name = v8::String::new(scope, name).unwrap();
+            let source_map_url = v8::String::new(scope, "").unwrap();
+            let origin = v8::ScriptOrigin::new(scope, name.into(), 0, 0, false, 123, source_map_url.into(), true, false, false);
+
+            let scope = &mut TryCatch::new(scope);
+            let value = v8::Script::compile(scope, source, Some(&origin))
+                .and_then(|script| script.run(scope));
+
+            match (value, scope.exception()) {
+                (Some(value), _) => Ok(Global::new(scope, value)),
+                // there is no exception to report when the execution has been terminated
+                (None, Some(exception)) if !scope.is_execution_terminating() =>
+                    Err(ScriptException::from_v8_exception(scope, exception, hooks).into()),
+                (None, _) => Err(generic_error("Execution has been terminated"))
+            }
+        };
 
-                self.cleanup_wakeup();
+        self.cleanup_wakeup();
 
-                let res = res?;
+        res
+    }
 
-                loop {
-                    if let Some(result) = self.poll_and_wait().await {
-                        break result;
-                    }
-                }?;
+    // waits for the value to settle if it's a promise
+    async fn resolve_value(&mut self, value: Global<Value>) -> Result<Global<Value>, AnyError> {
+        let is_promise = {
+            let runtime = self.runtime.as_mut().unwrap();
+            let scope = &mut runtime.handle_scope();
+            Local::new(scope, &value).is_promise()
+        };
+
+        if !is_promise {
+            return Ok(value);
+        }
+
+        self.run_event_loop().await?;
+
+        let runtime = self.runtime.as_mut().unwrap();
+        let hooks = self.hooks.as_ref();
+        let scope = &mut runtime.handle_scope();
+        let promise = Local::<Promise>::try_from(Local::new(scope, &value)).unwrap();
+
+        match promise.state() {
+            PromiseState::Fulfilled => {
+                let result = promise.result(scope);
+                Ok(Global::new(scope, result))
+            }
+            PromiseState::Rejected => {
+                let exception = promise.result(scope);
+                Err(ScriptException::from_v8_exception(scope, exception, hooks).into())
+            }
+            PromiseState::Pending => Err(generic_error("Promise is still pending but the event loop has already resolved"))
+        }
+    }
+
+    // serializing runs code of the script (toJSON, getters, proxy traps), so it happens in a wakeup
+    fn serialize_result(&mut self, value: Global<Value>) -> Result<ScriptResult, AnyError> {
+        self.prepare_wakeup()?;
+
+        let data = self.serialize_value(&value);
+        let serialized_data = self.serialize_value_binary(&value);
+
+        self.cleanup_wakeup();
+
+        // a terminated serialization results in null or an empty buffer
+        if let Some(reason) = self.resource_table().termination_reason {
+            return Err(reason.into());
+        }
+
+        Ok(ScriptResult { data, serialized_data })
+    }
+
+    // serializes the value using JSON.stringify
+    // values that can't be represented as JSON (undefined, functions, cyclic objects, ...) result in null
+    fn serialize_value(&mut self, value: &Global<Value>) -> String {
+        let runtime = self.runtime.as_mut().unwrap();
+        let scope = &mut runtime.handle_scope();
+        let value = Local::new(scope, value);
+
+        if value.is_undefined() || value.is_function() || value.is_symbol() {
+            return "null".to_string();
+        }
+
+        // JSON.stringify throws for cyclic objects and BigInts
+        let scope = &mut TryCatch::new(scope);
+        match json::stringify(scope, value) {
+            Some(json) => json.to_rust_string_lossy(scope),
+            None => "null".to_string()
+        }
+    }
+
+    // serializes the value using V8's ValueSerializer, which is the format of Isolator.serialize in JS
+    // values that can't be cloned (functions, symbols, ...) result in an empty buffer
+    fn serialize_value_binary(&mut self, value: &Global<Value>) -> Vec<u8> {
+        let runtime = self.runtime.as_mut().unwrap();
+        let scope = &mut runtime.handle_scope();
+        let value = Local::new(scope, value);
+
+        let scope = &mut TryCatch::new(scope);
+        let context = scope.get_current_context();
+        let mut serializer = v8::ValueSerializer::new(scope, Box::new(ResultSerializer));
+        serializer.write_header();
+        match serializer.write_value(context, value) {
+            Some(true) => serializer.release(),
+            _ => Vec::new()
+        }
+    }
+
+    async fn drive_execution(&mut self, script_context: ScriptContext) -> Result<Global<Value>, AnyError> {
+        match script_context {
+            ScriptContext::Default(script) => {
+                let res = self.execute_classic_script(&script.name, script.content.as_str())?;
+
+                self.run_event_loop().await?;
 
-                Ok(Some(res))
+                self.resolve_value(res).await
             }
             ScriptContext::Module(script) => {
-                let specifier = ModuleSpecifier::parse(&format!("https://isolator/{}", script.name)).unwrap();
+                let specifier = module_specifier(&script.name)?;
+
+                // a module with the same name replaces the previous one for later imports
+                // loading waits for module resource requests, so only the evaluation happens in a wakeup
+                let runtime = self.runtime.as_mut().unwrap();
+                let module_id = runtime.load_side_module(&specifier, Some(script.content)).await?;
 
                 self.prepare_wakeup()?;
 
                 let runtime = self.runtime.as_mut().unwrap();
-                let module_id = runtime.load_side_module(&specifier, Some(script.content)).await?;
                 let receiver = runtime.mod_evaluate(module_id);
 
                 self.cleanup_wakeup();
 
-                loop {
-                    if let Some(result) = self.poll_and_wait().await {
-                        break result;
-                    }
-                }?;
+                self.run_event_loop().await?;
 
                 receiver.await??;
-                Ok(None)
+
+                // deno_core keeps the module under its name, so importing it doesn't load or evaluate it again
+                let namespace = self.execute_classic_script(
+                    "<module-result>",
+                    &format!("import({}).then((ns) => 'default' in ns ? ns.default : ns)", serde_json::to_string(specifier.as_str())?),
+                )?;
+                self.resolve_value(namespace).await
+            }
+            ScriptContext::Invoke(invocation) => {
+                let args = if invocation.args_json.is_empty() {
+                    serde_json::Value::Array(vec![])
+                } else {
+                    serde_json::from_str(&invocation.args_json)?
+                };
+                if !args.is_array() {
+                    return Err(generic_error("The arguments of the function must be a JSON array"));
+                }
+
+                // modules that haven't been loaded yet are requested from the client
+                let target = match invocation.module {
+                    Some(module) => format!("await import({})", serde_json::to_string(module_specifier(&module)?.as_str())?),
+                    None => "globalThis".to_string(),
+                };
+                let res = self.execute_classic_script(
+                    "<invoke>",
+                    &format!(
+                        "(async () => {{
+                            const target = {};
+                            const name = {};
+                            if (typeof target[name] !== 'function') {{
+                                throw new TypeError(`${{name}} is not a function`);
+                            }}
+                            return await target[name](...{});
+                        }})()",
+                        target,
+                        serde_json::to_string(&invocation.export_name)?,
+                        serde_json::to_string(&args)?,
+                    ),
+                )?;
+
+                self.run_event_loop().await?;
+
+                self.resolve_value(res).await
             }
         }
     }
 
-    pub async fn execute_script(&mut self, script_context: ScriptContext) -> Result<Option<Global<Value>>, AnyError> {
-        let execution_time_limit = {
+    // executes the script and returns its result together with what it has consumed
+    pub async fn execute_script(
+        &mut self,
+        script_context: ScriptContext,
+        limits: ScriptLimits,
+        script_terminated_sender: &mpsc::Sender<oneshot::Sender<()>>,
+    ) -> (Result<ScriptResult, AnyError>, ScriptUsage) {
+        // the execution time limit of the isolate is shared by all its scripts
+        let (execution_deadline, termination_reason) = {
             let resource_table = &mut *self.resource_table();
-            resource_table.execution_time_limit
+            resource_table.start_script(limits);
+            let execution_deadline = resource_table.execution_time_left()
+                .map(|execution_time_left| tokio::time::Instant::now() + execution_time_left);
+            (execution_deadline, resource_table.termination_reason)
         };
 
-        if let Some(execution_time_limit) = execution_time_limit {
-            tokio::select! {
-                res = self.drive_execution(script_context) => res,
-                // this stops the execution loop from outside
-                // can only kick in between two wakeups and is therefore not able to interrupt CPU intensive work
-                _ = tokio::time::sleep(execution_time_limit) =>
-                    Err(generic_error("Isolate has run out of execution time").into())
+        let state = self.state.clone();
+        let res = match termination_reason {
+            // the script has been cancelled between being marked as running and being started
+            Some(reason) => Err(reason.into()),
+            None => {
+                let res = tokio::select! {
+                    res = async {
+                        let value = self.drive_execution(script_context).await?;
+                        self.serialize_result(value)
+                    } => res,
+                    // this stops the execution loop from outside
+                    // can only kick in between two wakeups, CPU intensive work is terminated by the cpu_time_manager
+                    _ = sleep_until(execution_deadline) => Err(TerminationReason::ExecutionTimeExceeded.into()),
+                    // wakes up scripts that are waiting for timers or resource requests when they are terminated
+                    _ = state.terminated.notified() => Err(generic_error("Execution has been terminated")),
+                };
+
+                if res.is_err() {
+                    // the reason is kept for the result, but the code handling the cancelled requests has to run
+                    let termination_reason = self.resource_table().termination_reason.take();
+                    self.runtime.as_mut().unwrap().v8_isolate().cancel_terminate_execution();
+
+                    self.clear_leftovers(script_terminated_sender).await;
+
+                    // the promises of the cancelled requests are settled here so their continuations don't run
+                    // in the next script, this is still part of the script so it's bound by the same limits
+                    tokio::select! {
+                        _ = self.run_event_loop() => {},
+                        _ = sleep_until(execution_deadline) => {},
+                        _ = state.terminated.notified() => {},
+                    }
+
+                    // the continuations might have started new timers or resource requests
+                    self.clear_leftovers(script_terminated_sender).await;
+
+                    if termination_reason.is_some() {
+                        self.resource_table().termination_reason = termination_reason;
+                    }
+                }
+
+                res
             }
-        } else {
-            self.drive_execution(script_context).await
+        };
+
+        // wakeups are never held across awaits, but an open one must not be left behind on an idle isolate
+        // when the execution has been dropped by the timeout or a termination
+        if self.resource_table().current_wakeup.is_some() {
+            self.cleanup_wakeup();
+        }
+
+        let resource_table = &mut *self.resource_table();
+        let usage = resource_table.finish_script();
+
+        let res = match resource_table.termination_reason {
+            // V8 only reports that the execution has been terminated but not why
+            Some(reason) if res.is_err() => Err(reason.into()),
+            // the error of the limit might have been turned into another exception by the script,
+            // a script that has recovered from it succeeds and only reports the limit in its usage
+            _ if res.is_err() && usage.resource_requests_limit_exceeded =>
+                Err(TerminationReason::ResourceLimitExceeded.into()),
+            _ => res
+        };
+
+        (res, usage)
+    }
+
+    // a failed script leaves its timers and resource requests behind, they would keep the next scripts waiting
+    // the messaging task rejects the pending resource requests and answers once it's done
+    async fn clear_leftovers(&mut self, script_terminated_sender: &mpsc::Sender<oneshot::Sender<()>>) {
+        self.clear_timers();
+
+        let (done_sender, done_receiver) = oneshot::channel();
+        if let Ok(_) = script_terminated_sender.send(done_sender).await {
+            let _ = done_receiver.await;
         }
     }
 
@@ -347,4 +1074,219 @@ impl Drop for WrappedRuntime {
     fn drop(&mut self) {
         self.unregister_globally();
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exception_name_and_message_of_errors() {
+        // throw new TypeError("message")
+        assert_eq!(
+            exception_name_and_message(Some("TypeError".into()), Some("message".into()), "Uncaught TypeError: message"),
+            ("TypeError".into(), "message".into())
+        );
+        // throw new TypeError()
+        assert_eq!(
+            exception_name_and_message(Some("TypeError".into()), Some("".into()), "Uncaught TypeError"),
+            ("TypeError".into(), "".into())
+        );
+        // names that aren't identifiers are kept as they are
+        assert_eq!(
+            exception_name_and_message(Some("My.Error".into()), Some("a: b".into()), "Uncaught My.Error: a: b"),
+            ("My.Error".into(), "a: b".into())
+        );
+        // objects that only have one of the properties
+        assert_eq!(
+            exception_name_and_message(None, Some("message".into()), "Uncaught #<Object>"),
+            ("".into(), "message".into())
+        );
+    }
+
+    #[test]
+    fn exception_name_and_message_of_other_values() {
+        // throw "foo: bar"
+        assert_eq!(
+            exception_name_and_message(None, None, "Uncaught foo: bar"),
+            ("".into(), "foo: bar".into())
+        );
+        // throw {}
+        assert_eq!(
+            exception_name_and_message(None, None, "Uncaught #<Object>"),
+            ("".into(), "#<Object>".into())
+        );
+        // throw ""
+        assert_eq!(
+            exception_name_and_message(None, None, "Uncaught"),
+            ("".into(), "".into())
+        );
+    }
+
+    #[test]
+    fn format_stack_of_frames() {
+        let mut named = JsStackFrame::from_location(Some("default".into()), Some(3), Some(11));
+        named.function_name = Some("handler".into());
+        let anonymous = JsStackFrame::from_location(Some("default".into()), Some(5), Some(1));
+
+        assert_eq!(
+            format_stack("Uncaught TypeError: message", &[named, anonymous]),
+            "TypeError: message\n    at handler (default:3:11)\n    at default:5:1"
+        );
+        assert_eq!(format_stack("Uncaught foo", &[]), "foo");
+    }
+
+    #[test]
+    fn strip_uncaught_prefix_of_messages() {
+        assert_eq!(strip_uncaught_prefix("Uncaught Error: message"), "Error: message");
+        assert_eq!(strip_uncaught_prefix("Uncaught (in promise) Error: message"), "Error: message");
+        assert_eq!(strip_uncaught_prefix("Uncaught"), "");
+        assert_eq!(strip_uncaught_prefix("Error: message"), "Error: message");
+    }
+
+    fn millis(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    // a start of the current script that lies the given milliseconds in the past
+    fn started_ago(millis: u64) -> Option<Instant> {
+        Instant::now().checked_sub(Duration::from_millis(millis))
+    }
+
+    #[test]
+    fn script_usage_of_consecutive_scripts() {
+        let mut table = ExecutionResourceTable {
+            cpu_time: millis(10),
+            resource_requests_count: 3,
+            heap_usage: 100,
+            ..Default::default()
+        };
+
+        table.start_script(ScriptLimits::default());
+        table.started_at = started_ago(20);
+        table.cpu_time += millis(5);
+        table.resource_requests_count += 2;
+        table.heap_usage = 300;
+        table.script_peak_heap_usage = 300;
+        table.resource_requests_limit_exceeded = true;
+        let usage = table.finish_script();
+
+        assert_eq!(usage.cpu_time, millis(5));
+        assert_eq!(usage.resource_requests_count, 2);
+        assert_eq!(usage.peak_heap_usage, 300);
+        assert!(usage.resource_requests_limit_exceeded);
+        assert!(usage.execution_time >= millis(20));
+        assert!(table.started_at.is_none());
+        assert!(table.execution_time >= millis(20));
+
+        table.start_script(ScriptLimits::default());
+        table.started_at = started_ago(30);
+        table.cpu_time += millis(1);
+        table.resource_requests_count += 1;
+        let usage = table.finish_script();
+
+        // only what has been consumed since the start of the script is counted
+        assert_eq!(usage.cpu_time, millis(1));
+        assert_eq!(usage.resource_requests_count, 1);
+        assert_eq!(usage.peak_heap_usage, 300);
+        assert!(!usage.resource_requests_limit_exceeded);
+        assert!(usage.execution_time >= millis(30));
+        assert!(table.execution_time >= millis(50));
+        assert_eq!(table.cpu_time, millis(16));
+        assert_eq!(table.resource_requests_count, 6);
+    }
+
+    #[test]
+    fn cpu_time_exceeded_of_script_and_isolate() {
+        let mut table = ExecutionResourceTable {
+            cpu_time_limit: Some(millis(100)),
+            cpu_time: millis(50),
+            ..Default::default()
+        };
+
+        table.start_script(ScriptLimits { cpu_time_limit: Some(millis(10)), ..Default::default() });
+        assert!(!table.cpu_time_exceeded());
+        // the limit itself can still be used up
+        table.cpu_time = millis(60);
+        assert!(!table.cpu_time_exceeded());
+        table.cpu_time = millis(61);
+        assert!(table.cpu_time_exceeded());
+
+        // the limit of the script doesn't apply to the next one
+        table.finish_script();
+        table.start_script(ScriptLimits::default());
+        assert!(!table.cpu_time_exceeded());
+        table.cpu_time = millis(100);
+        assert!(!table.cpu_time_exceeded());
+        table.cpu_time = millis(101);
+        assert!(table.cpu_time_exceeded());
+
+        // a script can't exceed the limit of the isolate even if its own limit is higher
+        table.finish_script();
+        table.start_script(ScriptLimits { cpu_time_limit: Some(millis(1000)), ..Default::default() });
+        assert!(table.cpu_time_exceeded());
+    }
+
+    #[test]
+    fn execution_time_left_of_script_and_isolate() {
+        let mut table = ExecutionResourceTable::default();
+        table.start_script(ScriptLimits::default());
+        assert_eq!(table.execution_time_left(), None);
+        table.finish_script();
+
+        // the isolate has less time left than the script
+        table.execution_time_limit = Some(millis(100));
+        table.execution_time = millis(90);
+        table.start_script(ScriptLimits { execution_time_limit: Some(millis(50)), ..Default::default() });
+        let left = table.execution_time_left().unwrap();
+        assert!(left <= millis(10) && left > millis(0));
+        table.finish_script();
+
+        // the script has less time left than the isolate
+        table.execution_time_limit = Some(millis(10_000));
+        table.execution_time = Duration::ZERO;
+        table.start_script(ScriptLimits { execution_time_limit: Some(millis(50)), ..Default::default() });
+        table.started_at = started_ago(20);
+        let left = table.execution_time_left().unwrap();
+        assert!(left <= millis(30) && left > millis(0));
+        assert!(!table.execution_time_exceeded());
+
+        table.started_at = started_ago(50);
+        assert_eq!(table.execution_time_left(), Some(Duration::ZERO));
+        assert!(table.execution_time_exceeded());
+        table.finish_script();
+
+        // the time of previous scripts only counts against the limit of the isolate
+        table.start_script(ScriptLimits { execution_time_limit: Some(millis(50)), ..Default::default() });
+        let left = table.execution_time_left().unwrap();
+        assert!(left <= millis(50) && left > millis(40));
+    }
+
+    #[test]
+    fn resource_requests_limit_reached_of_script_and_isolate() {
+        let mut table = ExecutionResourceTable {
+            resource_requests_limit: Some(5),
+            resource_requests_count: 2,
+            ..Default::default()
+        };
+
+        table.start_script(ScriptLimits { resource_requests_limit: Some(2), ..Default::default() });
+        assert!(!table.resource_requests_limit_reached());
+        table.resource_requests_count = 3;
+        assert!(!table.resource_requests_limit_reached());
+        // the script has made as many requests as it's allowed to
+        table.resource_requests_count = 4;
+        assert!(table.resource_requests_limit_reached());
+
+        table.finish_script();
+        table.start_script(ScriptLimits::default());
+        assert!(!table.resource_requests_limit_reached());
+        table.resource_requests_count = 5;
+        assert!(table.resource_requests_limit_reached());
+
+        // a script can't make more requests than the isolate even if its own limit is higher
+        table.finish_script();
+        table.start_script(ScriptLimits { resource_requests_limit: Some(100), ..Default::default() });
+        assert!(table.resource_requests_limit_reached());
+    }
 }
\ No newline at end of file
@@ -10,6 +10,11 @@ use isolator::{
     DrainResponse,
     KillRequest,
     KillResponse,
+    IsolateQueuedMessage,
+    IsolateStartedMessage,
+    get_status_response::IsolateStatus,
+    isolate_response::Message::{QueuedMessage, StartedMessage},
+    isolate_request::Message::{InitializeMessage, ScriptScheduleMessage, FunctionInvokeMessage, ScriptCancelMessage},
 };
 use tonic::{Status, Response, Request, Streaming};
 use std::pin::Pin;
@@ -17,46 +22,184 @@ use futures_core::Stream;
 use futures_util::StreamExt;
 use std::sync::{Arc};
 use crate::GlobalState;
-use crate::manager::ServiceChannelPair;
-use tokio::sync::mpsc;
+use crate::manager::{ServiceChannelPair, ServiceConnection};
+use crate::runtime::SharedRuntimeState;
+use tokio::sync::{mpsc, oneshot};
 use std::process;
 use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::OwnedSemaphorePermit;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
 
 pub mod isolator {
     tonic::include_proto!("isolator");
 }
 
+// how often the isolate count is checked while draining
+const DRAIN_INTERVAL: Duration = Duration::from_millis(100);
+
+// every isolate holds a thread permit from before it's scheduled until its thread has finished,
+// so this also counts isolates that haven't been registered yet
+fn running_isolates(state: &GlobalState) -> usize {
+    state.config.thread_pool_size.saturating_sub(state.threads.available_permits())
+}
+
+// takes a client out of the wait queue when it stops waiting
+struct QueueGuard(Arc<GlobalState>);
+
+impl Drop for QueueGuard {
+    fn drop(&mut self) {
+        self.0.queue_size.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// waits for a free thread until the configured max queue wait has been reached
+async fn acquire_thread(state: &GlobalState) -> Result<OwnedSemaphorePermit, Status> {
+    let acquire = state.threads.clone().acquire_owned();
+    let res = match state.config.max_queue_wait {
+        Some(max_queue_wait) => tokio::time::timeout(max_queue_wait, acquire).await
+            .map_err(|_| Status::resource_exhausted("timed out waiting for a free thread"))?,
+        None => acquire.await
+    };
+
+    // the semaphore is never closed
+    Ok(res.unwrap())
+}
+
 pub struct IsolatorService {
     pub state: Arc<GlobalState>,
-    pub scheduler: mpsc::Sender<ServiceChannelPair>,
-    pub accept_requests: AtomicBool
+    pub scheduler: mpsc::Sender<ServiceConnection>,
+    pub accept_requests: Arc<AtomicBool>,
+    // notified when the server should shut down gracefully
+    pub shutdown: Arc<Notify>,
+}
+
+impl IsolatorService {
+    // stops accepting isolates and reports the count of running isolates whenever it changes
+    fn drain_progress(&self, request: DrainRequest) -> impl Stream<Item=Result<DrainResponse, Status>> + Send + 'static {
+        self.accept_requests.store(false, Ordering::Relaxed);
+
+        let deadline = if request.timeout == 0 {
+            None
+        } else {
+            Some(Instant::now() + Duration::from_millis(request.timeout))
+        };
+
+        let state = self.state.clone();
+        let shutdown = self.shutdown.clone();
+
+        async_stream::try_stream! {
+            let mut last_count = None;
+            loop {
+                let isolate_count = running_isolates(&state);
+                if last_count != Some(isolate_count) {
+                    last_count = Some(isolate_count);
+                    yield DrainResponse {
+                        isolate_count: isolate_count as u32,
+                        drained: isolate_count == 0,
+                    };
+                }
+
+                if isolate_count == 0 || !(request.wait || request.shutdown) {
+                    break;
+                }
+                if let Some(deadline) = deadline {
+                    if Instant::now() >= deadline {
+                        break;
+                    }
+                }
+
+                tokio::time::sleep(DRAIN_INTERVAL).await;
+            }
+
+            if request.shutdown {
+                // the server only shuts down after all streams have been closed
+                for runtime in state.runtimes.lock().unwrap().values() {
+                    runtime.kill();
+                }
+                shutdown.notify_one();
+            }
+        }
+    }
 }
 
 #[tonic::async_trait]
 impl Isolator for IsolatorService {
     type AcquireIsolateStream = Pin<Box<dyn Stream<Item=Result<IsolateResponse, Status>> + Send + 'static>>;
+    type WatchDrainStream = Pin<Box<dyn Stream<Item=Result<DrainResponse, Status>> + Send + 'static>>;
 
     async fn acquire_isolate(&self, request: Request<Streaming<IsolateRequest>>) -> Result<Response<Self::AcquireIsolateStream>, Status> {
         if !self.accept_requests.load(Ordering::Relaxed) {
             return Err(Status::unavailable("instance has been drained"))
         }
 
-        let (to_sender, to_receiver) = mpsc::channel(10);
-        let (from_sender, mut from_receiver) = mpsc::channel(10);
-
-        let service_c = ServiceChannelPair {
-            receiver: to_receiver,
-            sender: from_sender,
+        // clients only have to wait if all threads are busy and the wait queue isn't full
+        let permit = self.state.threads.clone().try_acquire_owned().ok();
+        let queue_guard = if permit.is_none() {
+            let position = self.state.queue_size.fetch_add(1, Ordering::SeqCst) + 1;
+            let guard = QueueGuard(self.state.clone());
+            if position > self.state.config.max_queue_size {
+                return Err(Status::resource_exhausted("all threads are busy and the wait queue is full"))
+            }
+            Some((guard, position))
+        } else {
+            None
         };
 
-        if let Err(_) = self.scheduler.send(service_c).await {
-            panic!("Thread scheduler unavailable");
-        }
+        let state = self.state.clone();
+        let scheduler = self.scheduler.clone();
+        let accept_requests = self.accept_requests.clone();
         let mut stream = request.into_inner();
 
         let output = async_stream::try_stream! {
+            let permit = match permit {
+                Some(permit) => permit,
+                None => {
+                    // the guard keeps the client in the wait queue until it has acquired a thread
+                    let (_guard, position) = queue_guard.unwrap();
+                    yield IsolateResponse {
+                        message: Some(QueuedMessage(IsolateQueuedMessage { position: position as u32 }))
+                    };
+                    acquire_thread(&state).await?
+                }
+            };
+
+            // the instance might have been drained while the client was waiting
+            if !accept_requests.load(Ordering::Relaxed) {
+                Err(Status::unavailable("instance has been drained"))?;
+            }
+
+            let (to_sender, to_receiver) = mpsc::channel(10);
+            let (from_sender, mut from_receiver) = mpsc::channel(10);
+            let (kill_sender, mut kill_receiver) = oneshot::channel();
+            let runtime_state = Arc::new(SharedRuntimeState::new(&state.config, kill_sender));
+
+            let connection = ServiceConnection {
+                channels: ServiceChannelPair {
+                    receiver: to_receiver,
+                    sender: from_sender,
+                },
+                runtime_state: runtime_state.clone(),
+                permit,
+            };
+
+            if let Err(_) = scheduler.send(connection).await {
+                panic!("Thread scheduler unavailable");
+            }
+
+            yield IsolateResponse {
+                message: Some(StartedMessage(IsolateStartedMessage {}))
+            };
+
+            let mut killed = false;
+            let mut rejected = None;
             loop {
                 tokio::select! {
+                    res = &mut kill_receiver => {
+                        // the sender is only dropped without sending when the runtime has already stopped
+                        killed = res.is_ok();
+                        break;
+                    }
                     resp = from_receiver.recv() => {
                         if let Some(resp) = resp {
                             yield IsolateResponse {message: Some(resp)}
@@ -67,9 +210,28 @@ impl Isolator for IsolatorService {
                     req = stream.next() => {
                         if let Some(req) = req {
                             if let Ok(req) = req {
-                                if let Some(msg) = req.message {
-                                    let res = to_sender.send(msg).await;
-                                    if let Err(_) = res { break; }
+                                match req.message {
+                                    // cancelling can't go through the runtime thread because it might be busy
+                                    Some(ScriptCancelMessage(msg)) => {
+                                        runtime_state.cancel_script(&msg.nonce);
+                                    }
+                                    Some(msg) => {
+                                        // invocations are tracked like scripts so they can be cancelled as well
+                                        match &msg {
+                                            ScriptScheduleMessage(msg) => runtime_state.schedule_script(msg.nonce.clone()),
+                                            FunctionInvokeMessage(msg) => runtime_state.schedule_script(msg.nonce.clone()),
+                                            InitializeMessage(msg) => {
+                                                if let Err(e) = state.config.heap_limits(msg.soft_heap_limit, msg.hard_heap_limit) {
+                                                    rejected = Some(Status::invalid_argument(e));
+                                                    break;
+                                                }
+                                            }
+                                            _ => {}
+                                        }
+                                        let res = to_sender.send(msg).await;
+                                        if let Err(_) = res { break; }
+                                    }
+                                    None => {}
                                 }
                             } else {
                                 break;
@@ -80,22 +242,74 @@ impl Isolator for IsolatorService {
                     }
                 }
             }
+
+            if killed {
+                Err(Status::aborted("isolate has been killed"))?;
+            }
+            if let Some(status) = rejected {
+                Err(status)?;
+            }
         };
 
         Ok(Response::new(Box::pin(output) as Self::AcquireIsolateStream))
     }
 
-    async fn kill_isolates(&self, _request: Request<KillIsolatesRequest>) -> Result<Response<KillIsolatesResponse>, Status> {
-        Ok(Response::new(KillIsolatesResponse::default()))
+    async fn kill_isolates(&self, request: Request<KillIsolatesRequest>) -> Result<Response<KillIsolatesResponse>, Status> {
+        let request = request.into_inner();
+
+        let runtimes_guard = self.state.runtimes.lock().unwrap();
+        let runtimes = &*runtimes_guard;
+
+        let killed_ids = runtimes.iter()
+            .filter(|(id, _)| request.all || request.isolate_ids.contains(id))
+            .filter(|(_, runtime)| runtime.kill())
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        Ok(Response::new(KillIsolatesResponse { killed_ids }))
     }
 
     async fn get_status(&self, _request: Request<GetStatusRequest>) -> Result<Response<GetStatusResponse>, Status> {
-        Ok(Response::new(GetStatusResponse::default()))
+        let runtimes_guard = self.state.runtimes.lock().unwrap();
+        let runtimes = &*runtimes_guard;
+
+        let isolates = runtimes.iter()
+            .map(|(id, runtime)| {
+                let resource_table = runtime.resource_table.lock().unwrap();
+                IsolateStatus {
+                    id: id.clone(),
+                    age: runtime.created_at.elapsed().as_millis() as u64,
+                    cpu_time: resource_table.cpu_time_elapsed().as_millis() as u64,
+                    execution_time: resource_table.execution_time_elapsed().as_millis() as u64,
+                    resource_requests_count: resource_table.resource_requests_count,
+                    heap_usage: resource_table.heap_usage as u64,
+                    in_wakeup: resource_table.current_wakeup.is_some(),
+                }
+            })
+            .collect::<Vec<IsolateStatus>>();
+
+        Ok(Response::new(GetStatusResponse {
+            isolate_count: isolates.len() as u32,
+            threads_left: self.state.threads.available_permits() as u32,
+            isolates,
+            queue_size: self.state.queue_size.load(Ordering::SeqCst) as u32,
+        }))
     }
 
-    async fn drain(&self, _request: Request<DrainRequest>) -> Result<Response<DrainResponse>, Status> {
-        self.accept_requests.store(false, Ordering::Relaxed);
-        Ok(Response::new(DrainResponse::default()))
+    async fn drain(&self, request: Request<DrainRequest>) -> Result<Response<DrainResponse>, Status> {
+        // answers with the last progress once waiting is over
+        let mut progress = Box::pin(self.drain_progress(request.into_inner()));
+        let mut response = DrainResponse::default();
+        while let Some(res) = progress.next().await {
+            response = res?;
+        }
+
+        Ok(Response::new(response))
+    }
+
+    async fn watch_drain(&self, request: Request<DrainRequest>) -> Result<Response<Self::WatchDrainStream>, Status> {
+        let progress = self.drain_progress(request.into_inner());
+        Ok(Response::new(Box::pin(progress) as Self::WatchDrainStream))
     }
 
     async fn kill(&self, _request: Request<KillRequest>) -> Result<Response<KillResponse>, Status> {